@@ -0,0 +1,87 @@
+//! World benchmarks
+//!
+//! Run with `cargo bench -p dotrix_core --bench world`
+use std::time::{Duration, Instant};
+
+use dotrix_core::{ecs::Entity, World};
+
+const ENTITIES: usize = 50_000;
+
+struct Position(f32, f32, f32);
+struct Velocity(f32, f32, f32);
+struct Projectile;
+
+fn spawn() -> (World, Vec<Entity>) {
+    let mut world = World::new();
+    let entities = world
+        .spawn((0..ENTITIES).map(|i| {
+            let i = i as f32;
+            (Position(i, i, i), Velocity(1.0, 0.0, 0.0))
+        }))
+        .into();
+    (world, entities)
+}
+
+fn measure<F: FnOnce() -> usize>(name: &str, f: F) {
+    let start = Instant::now();
+    let operations = f();
+    report(name, operations, start.elapsed());
+}
+
+fn report(name: &str, operations: usize, elapsed: Duration) {
+    println!(
+        "{:<24} {:>8} ops {:>12.3} ms {:>10.1} ns/op",
+        name,
+        operations,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_nanos() as f64 / operations as f64,
+    );
+}
+
+fn main() {
+    measure("spawn", || {
+        let (world, _) = spawn();
+        world.counter() as usize
+    });
+
+    let (world, entities) = spawn();
+    measure("query", || {
+        let mut count = 0;
+        for (position, velocity) in world.query::<(&mut Position, &Velocity)>() {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+            position.2 += velocity.2;
+            count += 1;
+        }
+        count
+    });
+
+    measure("get", || {
+        entities
+            .iter()
+            .filter(|&&entity| world.get::<(&Position,)>(entity).is_some())
+            .count()
+    });
+
+    let (mut world, entities) = spawn();
+    measure("insert", || {
+        for &entity in entities.iter() {
+            world.insert(entity, Projectile);
+        }
+        entities.len()
+    });
+
+    measure("remove", || {
+        for &entity in entities.iter() {
+            world.remove::<Projectile>(entity);
+        }
+        entities.len()
+    });
+
+    measure("exile", || {
+        for &entity in entities.iter() {
+            world.exile(entity);
+        }
+        entities.len()
+    });
+}
@@ -1,17 +1,21 @@
 use std::{
-    any::TypeId,
+    any::{Any, TypeId},
+    cell::UnsafeCell,
     collections::HashMap,
     time::{Duration, Instant},
 };
 
+use futures::{executor::LocalPool, task::LocalSpawnExt};
+use log::{error, warn};
+use rayon::prelude::*;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::{Window as WinitWindow, WindowBuilder},
 };
 
-use crate::ecs::{RunLevel, System, Systemized};
-use crate::{Assets, Id, Input, State, Window};
+use crate::ecs::{OrderError, RunLevel, System, Systemized};
+use crate::{Assets, Commands, Frame, Id, Input, State, Tasks, Window, World};
 
 /// Application data to maintain the process
 ///
@@ -20,6 +24,12 @@ pub struct Application {
     name: &'static str,
     scheduler: Scheduler,
     services: Services,
+    /// Global application state, boxed to keep the address stable for [`State`] service
+    current_state: Box<Id<State>>,
+    /// Becomes true after startup systems were executed
+    started: bool,
+    /// Executor of [`Tasks`]
+    pool: LocalPool,
 }
 
 impl Application {
@@ -29,12 +39,25 @@ impl Application {
             name,
             scheduler: Scheduler::new(),
             services: Services::new(),
+            current_state: Box::new(State::meta()),
+            started: false,
+            pool: LocalPool::new(),
         }
     }
 
     /// Adds a system to the [`Application`]
+    ///
+    /// Panics if [`crate::ecs::Before`] and [`crate::ecs::After`] options of the system make a
+    /// cycle with other systems of the same [`RunLevel`]
     pub fn add_system(&mut self, system: System) {
-        self.scheduler.add(system);
+        if let Err(error) = self.scheduler.add(system) {
+            panic!("{}", error);
+        }
+    }
+
+    /// Returns true if a system with the label or the full path of its function was added
+    pub fn has_system(&self, label: &str) -> bool {
+        self.scheduler.contains(label)
     }
 
     /// Adds a service to the [`Application`]
@@ -49,6 +72,19 @@ impl Application {
             .expect("Application services does not exist")
     }
 
+    /// Returns a service of the [`Application`] if it exists
+    pub fn try_service<T: IntoService>(&mut self) -> Option<&mut T> {
+        self.services.get_mut::<T>()
+    }
+
+    /// Returns a service of the [`Application`], adding the default one if it does not exist
+    pub fn service_or_default<T: IntoService + Default>(&mut self) -> &mut T {
+        if self.services.get_mut::<T>().is_none() {
+            self.services.add(T::default());
+        }
+        self.service::<T>()
+    }
+
     /// Run the application
     pub fn run(self) {
         let event_loop = EventLoop::new();
@@ -58,6 +94,101 @@ impl Application {
 
         run(event_loop, window, self);
     }
+
+    /// Runs defined number of frames without a window and an event loop
+    ///
+    /// Startup systems are executed on the first call only, so the method can be called several
+    /// times to advance the application step by step. Use it together with
+    /// [`crate::Renderer::headless`] to render offscreen, e.g. in tests.
+    pub fn run_frames(&mut self, frames: usize) {
+        self.startup();
+        for _ in 0..frames {
+            self.tick();
+        }
+    }
+
+    /// Runs a single frame that lasts exactly `delta` without a window and an event loop
+    ///
+    /// [`Frame::delta`] and [`Frame::time`] are advanced by `delta` regardless of the
+    /// [`crate::frame::Clock`] used by the [`Frame`] service, so systems can be tested
+    /// deterministically.
+    pub fn step(&mut self, delta: Duration) {
+        self.startup();
+        if let Some(frame) = self.services.get_mut::<Frame>() {
+            frame.step(delta);
+        }
+        self.tick();
+    }
+
+    /// Fetches loaded assets, runs tasks and a frame
+    fn tick(&mut self) {
+        if let Some(assets) = self.services.get_mut::<Assets>() {
+            assets.fetch();
+        }
+        self.run_tasks();
+        self.frame();
+    }
+
+    /// Starts spawned [`Tasks`] and runs the pool until all the tasks are stalled
+    fn run_tasks(&mut self) {
+        if let Some(tasks) = self.services.get_mut::<Tasks>() {
+            let spawner = self.pool.spawner();
+            for task in tasks.take() {
+                if let Err(e) = spawner.spawn_local(task) {
+                    error!("Task can't be spawned: {:?}", e);
+                }
+            }
+        }
+        self.pool.run_until_stalled();
+    }
+
+    /// Links services to the application state and runs startup systems once
+    fn startup(&mut self) {
+        if self.started {
+            return;
+        }
+        let current_state_ptr: *const Id<State> = &*self.current_state;
+        if let Some(state) = self.services.get_mut::<State>() {
+            state.set_pointer(current_state_ptr);
+        }
+        self.scheduler.warn_unknown_labels();
+        self.scheduler
+            .run_startup(&mut self.services, current_state_ptr);
+        self.services.apply_commands();
+        self.scheduler.run_hooks(&mut self.services);
+        self.started = true;
+    }
+
+    /// Runs all per-frame systems
+    fn frame(&mut self) {
+        let current_state_ptr: *const Id<State> = &*self.current_state;
+        let scheduler = &mut self.scheduler;
+        let services = &mut self.services;
+        // transitions made outside of systems
+        scheduler.run_hooks(services);
+        scheduler.run_bind(services, current_state_ptr);
+        let fixed_steps = services
+            .get::<Frame>()
+            .map(|frame| frame.fixed_steps())
+            .unwrap_or(0);
+        for _ in 0..fixed_steps {
+            scheduler.run_fixed_update(services, current_state_ptr);
+            services.apply_commands();
+            scheduler.run_hooks(services);
+        }
+        scheduler.run_update(services, current_state_ptr);
+        services.apply_commands();
+        scheduler.run_hooks(services);
+        scheduler.run_load(services, current_state_ptr);
+        scheduler.run_compute(services, current_state_ptr);
+        scheduler.run_pre_render(services, current_state_ptr);
+        scheduler.run_render(services, current_state_ptr);
+        scheduler.run_release(services, current_state_ptr);
+        scheduler.run_hooks(services);
+        if let Some(world) = services.get_mut::<World>() {
+            world.advance();
+        }
+    }
 }
 
 /// Service wrapper
@@ -83,41 +214,27 @@ pub trait IntoService: Sized + Send + Sync + 'static {
 impl<T: Sized + Send + Sync + 'static> IntoService for T {}
 
 /// Application run cycle
-fn run(
-    event_loop: EventLoop<()>,
-    winit_window: WinitWindow,
-    Application {
-        name,
-        mut scheduler,
-        mut services,
-        ..
-    }: Application,
-) {
-    // Global application state
-    let current_state = State::meta();
-    let current_state_ptr: *const Id<State> = &current_state;
-
-    let (mut pool, _spawner) = {
-        let local_pool = futures::executor::LocalPool::new();
-        let spawner = local_pool.spawner();
-        (local_pool, spawner)
-    };
+fn run(event_loop: EventLoop<()>, winit_window: WinitWindow, mut app: Application) {
     let mut last_update_inst = Instant::now();
 
     // !!! DO NOT CREATE SERVICES HERE !!!
 
-    if let Some(window) = services.get_mut::<Window>() {
+    if let Some(window) = app.services.get_mut::<Window>() {
         window.set(winit_window);
-        window.set_title(name);
+        window.set_title(app.name);
     }
 
-    if let Some(state) = services.get_mut::<State>() {
-        state.set_pointer(current_state_ptr);
-    }
+    app.startup();
 
-    scheduler.run_startup(&mut services, current_state_ptr);
+    let current_state_ptr: *const Id<State> = &*app.current_state;
 
     event_loop.run(move |event, _, control_flow| {
+        let Application {
+            scheduler,
+            services,
+            ..
+        } = &mut app;
+
         *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(10));
 
         if let Some(input) = services.get_mut::<Input>() {
@@ -134,7 +251,7 @@ fn run(
             .unwrap_or(false);
 
         if resize_requested {
-            scheduler.run_resize(&mut services, current_state_ptr);
+            scheduler.run_resize(services, current_state_ptr);
         }
 
         match event {
@@ -149,13 +266,13 @@ fn run(
                     }
                     last_update_inst = Instant::now();
                 }
-                pool.run_until_stalled();
+                app.run_tasks();
             }
             Event::WindowEvent {
                 event: WindowEvent::Resized(_),
                 ..
             } => {
-                scheduler.run_resize(&mut services, current_state_ptr);
+                scheduler.run_resize(services, current_state_ptr);
             }
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
@@ -164,13 +281,7 @@ fn run(
                 *control_flow = ControlFlow::Exit;
             }
             Event::RedrawRequested(_) => {
-                scheduler.run_bind(&mut services, current_state_ptr);
-                scheduler.run_update(&mut services, current_state_ptr);
-                scheduler.run_load(&mut services, current_state_ptr);
-                scheduler.run_compute(&mut services, current_state_ptr);
-                scheduler.run_pre_render(&mut services, current_state_ptr);
-                scheduler.run_render(&mut services, current_state_ptr);
-                scheduler.run_release(&mut services, current_state_ptr);
+                app.frame();
             }
             _ => {}
         }
@@ -178,10 +289,24 @@ fn run(
 }
 
 /// Services manager
+///
+/// Services are stored in cells, so systems of a batch can fetch different services mutably
+/// from a shared reference to the manager.
 pub struct Services {
-    storage: HashMap<TypeId, Box<dyn std::any::Any>>,
+    storage: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
 }
 
+// The map is never changed through a shared reference and services are `Send + Sync`. Access
+// to a service through a cell is coordinated by the scheduler: systems, that run in parallel,
+// never access the same service mutably.
+unsafe impl Sync for Services {}
+
+/// Cell of a service, that gives a mutable pointer to the service from a shared reference
+struct ServiceCell<T>(UnsafeCell<T>);
+
+// See `Services`
+unsafe impl<T: IntoService> Sync for ServiceCell<T> {}
+
 impl Services {
     pub(crate) fn new() -> Self {
         Self {
@@ -190,56 +315,277 @@ impl Services {
     }
 
     pub(crate) fn add<T: IntoService>(&mut self, service: T) {
-        self.storage.insert(TypeId::of::<T>(), Box::new(service));
+        self.storage.insert(
+            TypeId::of::<T>(),
+            Box::new(ServiceCell(UnsafeCell::new(service))),
+        );
     }
 
     pub(crate) fn get<T: IntoService>(&self) -> Option<&T> {
-        self.storage
-            .get(&TypeId::of::<T>())
-            .map(|srv| srv.downcast_ref::<T>().unwrap())
+        self.get_ptr::<T>().map(|srv| unsafe { &*srv })
     }
 
     pub(crate) fn get_mut<T: IntoService>(&mut self) -> Option<&mut T> {
         self.storage
             .get_mut(&TypeId::of::<T>())
-            .map(|srv| srv.downcast_mut::<T>().unwrap())
+            .map(|srv| srv.downcast_mut::<ServiceCell<T>>().unwrap().0.get_mut())
+    }
+
+    /// Returns pointer to the service in its cell
+    ///
+    /// No reference to the service is created, dereferencing of the pointer is safe as long as
+    /// the service is not accessed mutably through another pointer at the same time.
+    pub(crate) fn get_ptr<T: IntoService>(&self) -> Option<*mut T> {
+        self.storage
+            .get(&TypeId::of::<T>())
+            .map(|srv| srv.downcast_ref::<ServiceCell<T>>().unwrap().0.get())
+    }
+
+    /// Applies commands deferred by systems to the [`World`]
+    pub(crate) fn apply_commands(&mut self) {
+        let mut commands = match self.get_mut::<Commands>() {
+            Some(commands) if !commands.is_empty() => std::mem::take(commands),
+            _ => return,
+        };
+        if let Some(world) = self.get_mut::<World>() {
+            commands.apply(world);
+        }
+    }
+}
+
+/// Systems of a single [`RunLevel`]
+///
+/// Systems are split into batches, that are executed one after another. Systems of the same
+/// batch don't conflict with each other and run in parallel.
+#[derive(Default)]
+struct Stage {
+    systems: Vec<Box<dyn Systemized>>,
+    /// Batch index of each system
+    batches: Vec<usize>,
+}
+
+impl Stage {
+    fn add(&mut self, system: Box<dyn Systemized>) -> Result<(), OrderError> {
+        self.systems.push(system);
+        let order = self.check_labels().and_then(|_| self.sort());
+        if let Err(error) = order {
+            self.systems.pop();
+            return Err(error);
+        }
+        self.plan();
+        Ok(())
+    }
+
+    /// Checks that each label of [`crate::ecs::Before`] and [`crate::ecs::After`] options points
+    /// to one system at most
+    fn check_labels(&self) -> Result<(), OrderError> {
+        for system in self.systems.iter() {
+            let labels = system.before().iter().chain(system.after().iter());
+            for &label in labels.chain(system.label().iter()) {
+                let labeled = self
+                    .systems
+                    .iter()
+                    .filter(|other| other.is_labeled(label))
+                    .map(|other| other.name())
+                    .collect::<Vec<_>>();
+                if labeled.len() > 1 {
+                    return Err(OrderError::Ambiguous(label, labeled));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns labels of [`crate::ecs::Before`] and [`crate::ecs::After`] options, that point
+    /// to no system of the stage, together with names of systems having the options
+    fn unknown_labels(&self) -> Vec<(&'static str, &'static str)> {
+        let mut unknown = Vec::new();
+        for system in self.systems.iter() {
+            for &label in system.before().iter().chain(system.after().iter()) {
+                if !self.systems.iter().any(|other| other.is_labeled(label)) {
+                    unknown.push((system.name(), label));
+                }
+            }
+        }
+        unknown
+    }
+
+    /// Sorts systems respecting [`crate::ecs::Before`] and [`crate::ecs::After`] options
+    ///
+    /// From all systems, which dependencies are satisfied, the one with the highest priority goes
+    /// first. Systems of the same priority keep the order of addition.
+    fn sort(&mut self) -> Result<(), OrderError> {
+        let count = self.systems.len();
+        let mut dependencies = vec![0; count];
+        let mut dependents = vec![Vec::new(); count];
+        let mut predecessors = vec![Vec::new(); count];
+        for (i, system) in self.systems.iter().enumerate() {
+            for (j, other) in self.systems.iter().enumerate() {
+                if i != j && system.precedes(other.as_ref()) {
+                    dependencies[j] += 1;
+                    dependents[i].push(j);
+                    predecessors[j].push(i);
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(count);
+        let mut sorted = vec![false; count];
+        while order.len() < count {
+            let next = (0..count)
+                .filter(|&i| !sorted[i] && dependencies[i] == 0)
+                .max_by(|&i, &j| {
+                    let p1: u32 = self.systems[i].priority().into();
+                    let p2: u32 = self.systems[j].priority().into();
+                    p1.cmp(&p2).then(j.cmp(&i))
+                });
+
+            let next = match next {
+                Some(next) => next,
+                None => return Err(OrderError::Cycle(self.cycle(&sorted, &predecessors))),
+            };
+
+            sorted[next] = true;
+            for &dependent in dependents[next].iter() {
+                dependencies[dependent] -= 1;
+            }
+            order.push(next);
+        }
+
+        let mut systems = std::mem::take(&mut self.systems)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.systems = order
+            .into_iter()
+            .map(|i| systems[i].take().unwrap())
+            .collect();
+        Ok(())
+    }
+
+    /// Finds names of the systems forming a cycle among not sorted ones
+    ///
+    /// Each not sorted system waits for at least one not sorted predecessor, so walking
+    /// predecessors always ends up in a cycle.
+    fn cycle(&self, sorted: &[bool], predecessors: &[Vec<usize>]) -> Vec<&'static str> {
+        let mut path: Vec<usize> = Vec::new();
+        let mut current = (0..sorted.len()).find(|&i| !sorted[i]).unwrap();
+        while !path.contains(&current) {
+            path.push(current);
+            current = predecessors[current]
+                .iter()
+                .copied()
+                .find(|&i| !sorted[i])
+                .expect("Not sorted system must have a not sorted predecessor");
+        }
+        let start = path.iter().position(|&i| i == current).unwrap();
+        // the path goes against the execution order
+        path[start..]
+            .iter()
+            .chain(Some(&current))
+            .rev()
+            .map(|&i| self.systems[i].name())
+            .collect()
+    }
+
+    /// Assigns systems to batches
+    ///
+    /// Each system goes to the batch after the last one, that contains a conflicting or a
+    /// preceding system, so conflicting systems keep the sorting order.
+    fn plan(&mut self) {
+        self.batches.clear();
+        for (i, system) in self.systems.iter().enumerate() {
+            let batch = self.systems[0..i]
+                .iter()
+                .zip(self.batches.iter())
+                .filter(|(other, _)| {
+                    system.conflicts(other.as_ref()) || other.precedes(system.as_ref())
+                })
+                .map(|(_, batch)| batch + 1)
+                .max()
+                .unwrap_or(0);
+            self.batches.push(batch);
+        }
+    }
+
+    fn run(&mut self, services: &Services, state_ptr: *const Id<State>) {
+        let batches = self.batches.iter().max().map(|last| last + 1).unwrap_or(0);
+        for batch in 0..batches {
+            let state = unsafe { *state_ptr };
+            let mut systems = self
+                .systems
+                .iter_mut()
+                .zip(self.batches.iter())
+                .filter(|(_, b)| **b == batch)
+                .map(|(system, _)| system)
+                .collect::<Vec<_>>();
+
+            if systems.len() == 1 {
+                systems[0].run(services, state);
+                continue;
+            }
+
+            systems
+                .par_iter_mut()
+                .for_each(|system| system.run(services, state));
+        }
     }
 }
 
+/// Limit of hook passes per call, when hooks keep changing the state
+const MAX_HOOK_PASSES: usize = 16;
+
 /// Systems scheduler
 struct Scheduler {
-    startup: Vec<Box<dyn Systemized>>,
-    bind: Vec<Box<dyn Systemized>>,
-    update: Vec<Box<dyn Systemized>>,
-    load: Vec<Box<dyn Systemized>>,
-    compute: Vec<Box<dyn Systemized>>,
-    pre_render: Vec<Box<dyn Systemized>>,
-    render: Vec<Box<dyn Systemized>>,
-    release: Vec<Box<dyn Systemized>>,
-    resize: Vec<Box<dyn Systemized>>,
+    startup: Stage,
+    bind: Stage,
+    fixed_update: Stage,
+    update: Stage,
+    load: Stage,
+    compute: Stage,
+    pre_render: Stage,
+    render: Stage,
+    release: Stage,
+    resize: Stage,
+    /// Systems, that run on state transitions
+    hooks: Stage,
 }
 
 impl Scheduler {
     pub fn new() -> Self {
         Self {
-            startup: Vec::new(),
-            bind: Vec::new(),
-            update: Vec::new(),
-            load: Vec::new(),
-            compute: Vec::new(),
-            pre_render: Vec::new(),
-            render: Vec::new(),
-            release: Vec::new(),
-            resize: Vec::new(),
+            startup: Stage::default(),
+            bind: Stage::default(),
+            fixed_update: Stage::default(),
+            update: Stage::default(),
+            load: Stage::default(),
+            compute: Stage::default(),
+            pre_render: Stage::default(),
+            render: Stage::default(),
+            release: Stage::default(),
+            resize: Stage::default(),
+            hooks: Stage::default(),
         }
     }
 
-    pub fn add(&mut self, system: System) {
+    pub fn add(&mut self, system: System) -> Result<(), OrderError> {
         let System { data, run_level } = system;
 
-        let storage = match run_level {
+        if data.rules().iter().any(|rule| rule.is_hook()) {
+            if run_level != RunLevel::Update {
+                warn!(
+                    "Hook system `{}` runs on state transitions, its {:?} run level is ignored",
+                    data.name(),
+                    run_level
+                );
+            }
+            return self.hooks.add(data);
+        }
+
+        let stage = match run_level {
             RunLevel::Startup => &mut self.startup,
             RunLevel::Bind => &mut self.bind,
+            RunLevel::FixedUpdate => &mut self.fixed_update,
             RunLevel::Update => &mut self.update,
             RunLevel::Load => &mut self.load,
             RunLevel::Compute => &mut self.compute,
@@ -249,54 +595,528 @@ impl Scheduler {
             RunLevel::Resize => &mut self.resize,
         };
 
-        storage.push(data);
-        storage.sort_by(|s1, s2| {
-            let p1: u32 = s1.priority().into();
-            let p2: u32 = s2.priority().into();
-            p2.cmp(&p1)
-        });
+        stage.add(data)
     }
 
-    fn run(list: &mut [Box<dyn Systemized>], services: &mut Services, state_ptr: *const Id<State>) {
-        for system in list.iter_mut() {
-            let state = unsafe { *state_ptr };
-            system.run(services, state);
+    fn stages(&self) -> [&Stage; 11] {
+        [
+            &self.startup,
+            &self.bind,
+            &self.fixed_update,
+            &self.update,
+            &self.load,
+            &self.compute,
+            &self.pre_render,
+            &self.render,
+            &self.release,
+            &self.resize,
+            &self.hooks,
+        ]
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.stages()
+            .iter()
+            .any(|stage| stage.systems.iter().any(|system| system.is_labeled(label)))
+    }
+
+    /// Warns about ordering options, that point to no system of the same run level
+    ///
+    /// Systems are added one by one, so the check is done once all of them are added.
+    pub fn warn_unknown_labels(&self) {
+        for stage in self.stages() {
+            for (system, label) in stage.unknown_labels() {
+                warn!(
+                    "System `{}` is ordered relative to `{}`, but no system of its run level has \
+                    this label",
+                    system, label
+                );
+            }
         }
     }
 
     pub fn run_startup(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.startup, services, state_ptr);
+        self.startup.run(services, state_ptr);
     }
 
     pub fn run_bind(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.bind, services, state_ptr);
+        self.bind.run(services, state_ptr);
+    }
+
+    pub fn run_fixed_update(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
+        self.fixed_update.run(services, state_ptr);
     }
 
     pub fn run_update(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.update, services, state_ptr);
+        self.update.run(services, state_ptr);
     }
 
     pub fn run_load(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.load, services, state_ptr);
+        self.load.run(services, state_ptr);
     }
 
     pub fn run_compute(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.compute, services, state_ptr);
+        self.compute.run(services, state_ptr);
     }
 
     pub fn run_pre_render(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.pre_render, services, state_ptr);
+        self.pre_render.run(services, state_ptr);
     }
 
     pub fn run_render(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.render, services, state_ptr);
+        self.render.run(services, state_ptr);
     }
 
     pub fn run_release(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.release, services, state_ptr);
+        self.release.run(services, state_ptr);
     }
 
     pub fn run_resize(&mut self, services: &mut Services, state_ptr: *const Id<State>) {
-        Self::run(&mut self.resize, services, state_ptr);
+        self.resize.run(services, state_ptr);
+    }
+
+    /// Runs hook systems for state transitions, that happened since the last call
+    ///
+    /// Hooks are executed one by one in order of transitions. If hooks change the state too,
+    /// new transitions are handled in the same call, up to [`MAX_HOOK_PASSES`] times. The rest
+    /// of transitions is postponed to the next call then.
+    pub fn run_hooks(&mut self, services: &mut Services) {
+        for _ in 0..MAX_HOOK_PASSES {
+            let transitions = services
+                .get_mut::<State>()
+                .map(|state| state.take_transitions())
+                .unwrap_or_default();
+            if transitions.is_empty() {
+                return;
+            }
+            for transition in transitions {
+                for system in self.hooks.systems.iter_mut() {
+                    system.run_hook(services, transition);
+                }
+            }
+        }
+        warn!(
+            "State hooks keep changing the state after {} passes, postponing the rest",
+            MAX_HOOK_PASSES
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Application, Stage};
+    use crate::assets::{ImportError, Shader};
+    use crate::ecs::{
+        After, Before, Const, Exclusive, Label, Mut, OrderError, Priority, RunLevel, System,
+    };
+    use crate::frame::{self, ManualClock};
+    use crate::test_utils::{poll, TempDir};
+    use crate::{Assets, Frame, Id, State, Tasks};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Timeline(Vec<(Duration, Duration)>);
+
+    fn record(frame: Const<Frame>, mut timeline: Mut<Timeline>) {
+        timeline.0.push((frame.delta(), frame.time()));
+    }
+
+    #[test]
+    fn deterministic_steps() {
+        let clock = ManualClock::new();
+        let mut app = Application::new("Steps");
+        app.add_service(Frame::with_clock(clock.clone()));
+        app.add_service(Timeline::default());
+        app.add_system(System::from(frame::bind));
+        app.add_system(System::from(record));
+
+        app.step(Duration::from_millis(10));
+        app.step(Duration::from_millis(20));
+        clock.advance(Duration::from_millis(5));
+        app.run_frames(2);
+
+        let timeline = &app.service::<Timeline>().0;
+        assert_eq!(
+            timeline,
+            &[
+                (Duration::from_millis(10), Duration::from_millis(10)),
+                (Duration::from_millis(20), Duration::from_millis(30)),
+                (Duration::from_millis(5), Duration::from_millis(35)),
+                (Duration::from_millis(0), Duration::from_millis(35)),
+            ]
+        );
+    }
+
+    #[derive(Default)]
+    struct Counter(u32);
+
+    #[derive(Default)]
+    struct Total(u32);
+
+    fn count(mut counter: Mut<Counter>) {
+        counter.0 += 1;
+    }
+
+    fn read_counter(_counter: Const<Counter>, _frame: Const<Frame>) {}
+
+    fn read_frame(_frame: Const<Frame>) {}
+
+    fn sum(counter: Const<Counter>, mut total: Mut<Total>) {
+        total.0 += counter.0;
+    }
+
+    #[test]
+    fn parallel_batches() {
+        let mut stage = Stage::default();
+        stage
+            .add(System::from(count).with(Priority::High).data)
+            .unwrap();
+        stage.add(System::from(read_frame).data).unwrap();
+        stage.add(System::from(read_counter).data).unwrap();
+        stage
+            .add(System::from(sum).with(Priority::Low).data)
+            .unwrap();
+        assert_eq!(stage.batches, vec![0, 0, 1, 1]);
+
+        stage
+            .add(System::from(read_frame).with(Exclusive).data)
+            .unwrap();
+        assert_eq!(stage.batches, vec![0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn parallel_execution() {
+        let mut app = Application::new("Parallel");
+        app.add_service(Frame::new());
+        app.add_service(Counter::default());
+        app.add_service(Total::default());
+        app.add_system(System::from(count).with(Priority::High));
+        app.add_system(System::from(read_frame));
+        app.add_system(System::from(read_counter));
+        app.add_system(System::from(sum).with(Priority::Low));
+
+        app.run_frames(3);
+
+        assert_eq!(app.service::<Counter>().0, 3);
+        assert_eq!(app.service::<Total>().0, 6);
+    }
+
+    #[test]
+    fn fixed_update() {
+        let mut frame = Frame::with_clock(ManualClock::new());
+        frame.set_tick_rate(100);
+        let mut app = Application::new("FixedUpdate");
+        app.add_service(frame);
+        app.add_service(Counter::default());
+        app.add_system(System::from(frame::bind));
+        app.add_system(System::from(count).with(RunLevel::FixedUpdate));
+
+        app.step(Duration::from_millis(25));
+        assert_eq!(app.service::<Counter>().0, 2);
+        app.step(Duration::from_millis(4));
+        assert_eq!(app.service::<Counter>().0, 2);
+        app.step(Duration::from_millis(1));
+        assert_eq!(app.service::<Counter>().0, 3);
+        assert!(app.service::<Frame>().alpha() < f32::EPSILON);
+    }
+
+    struct Menu;
+    struct Level;
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    fn enter_menu(mut log: Mut<Log>) {
+        log.0.push("enter menu");
+    }
+
+    fn pause_menu(mut log: Mut<Log>) {
+        log.0.push("pause menu");
+    }
+
+    fn resume_menu(mut log: Mut<Log>) {
+        log.0.push("resume menu");
+    }
+
+    fn enter_level(mut log: Mut<Log>) {
+        log.0.push("enter level");
+    }
+
+    fn exit_level(mut log: Mut<Log>) {
+        log.0.push("exit level");
+    }
+
+    fn menu_update(mut log: Mut<Log>) {
+        log.0.push("menu");
+    }
+
+    fn startup(mut state: Mut<State>) {
+        state.push(Menu);
+    }
+
+    #[test]
+    fn state_hooks() {
+        let mut app = Application::new("Hooks");
+        app.add_service(State::default());
+        app.add_service(Log::default());
+        app.add_system(System::from(startup));
+        app.add_system(System::from(enter_menu).with(State::on_enter::<Menu>()));
+        app.add_system(System::from(pause_menu).with(State::on_pause::<Menu>()));
+        app.add_system(System::from(resume_menu).with(State::on_resume::<Menu>()));
+        app.add_system(System::from(enter_level).with(State::on_enter::<Level>()));
+        app.add_system(System::from(exit_level).with(State::on_exit::<Level>()));
+        app.add_system(System::from(menu_update).with(State::on::<Menu>()));
+
+        app.run_frames(2);
+        app.service::<State>().push(Level);
+        app.run_frames(2);
+        app.service::<State>().pop_any();
+        app.run_frames(1);
+
+        assert_eq!(
+            app.service::<Log>().0,
+            vec![
+                "enter menu",
+                "menu",
+                "menu",
+                "pause menu",
+                "enter level",
+                "exit level",
+                "resume menu",
+                "menu",
+            ]
+        );
+    }
+
+    struct Loop;
+
+    fn enter_loop(mut state: Mut<State>, mut log: Mut<Log>) {
+        log.0.push("enter loop");
+        state.push(Loop);
+    }
+
+    #[test]
+    fn endless_hooks() {
+        let mut app = Application::new("Endless hooks");
+        app.add_service(State::default());
+        app.add_service(Log::default());
+        app.add_system(System::from(enter_loop).with(State::on_enter::<Loop>()));
+
+        app.run_frames(0);
+        app.service::<State>().push(Loop);
+        app.scheduler.run_hooks(&mut app.services);
+        assert_eq!(app.service::<Log>().0.len(), super::MAX_HOOK_PASSES);
+
+        // the rest of transitions is handled by the next call
+        app.scheduler.run_hooks(&mut app.services);
+        assert_eq!(app.service::<Log>().0.len(), 2 * super::MAX_HOOK_PASSES);
+    }
+
+    fn names(stage: &Stage) -> Vec<&'static str> {
+        stage
+            .systems
+            .iter()
+            .map(|system| system.name().rsplit("::").next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn before_and_after() {
+        let mut stage = Stage::default();
+        stage
+            .add(
+                System::from(sum)
+                    .with(Priority::High)
+                    .with(Label("sum"))
+                    .data,
+            )
+            .unwrap();
+        stage
+            .add(System::from(count).with(Before("sum")).data)
+            .unwrap();
+        stage
+            .add(
+                System::from(read_frame)
+                    .with(After("dotrix_core::application::tests::count"))
+                    .with(Priority::Low)
+                    .data,
+            )
+            .unwrap();
+        stage.add(System::from(read_counter).data).unwrap();
+
+        assert_eq!(
+            names(&stage),
+            vec!["count", "sum", "read_counter", "read_frame"]
+        );
+        // read_frame doesn't conflict with count, but must wait for it
+        assert_eq!(stage.batches, vec![0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn ordering_cycle() {
+        let mut stage = Stage::default();
+        stage
+            .add(
+                System::from(count)
+                    .with(Label("count"))
+                    .with(After("read_frame"))
+                    .data,
+            )
+            .unwrap();
+        stage
+            .add(
+                System::from(sum)
+                    .with(Label("sum"))
+                    .with(After("count"))
+                    .data,
+            )
+            .unwrap();
+        let result = stage.add(
+            System::from(read_frame)
+                .with(Label("read_frame"))
+                .with(After("sum"))
+                .data,
+        );
+
+        match result {
+            Err(OrderError::Cycle(cycle)) => {
+                assert_eq!(cycle.len(), 4);
+                assert_eq!(cycle.first(), cycle.last());
+            }
+            _ => panic!("Cycle must be detected"),
+        }
+        assert_eq!(names(&stage), vec!["count", "sum"]);
+
+        // the first system waits for the cycle, but is not a part of it
+        let mut stage = Stage::default();
+        stage
+            .add(System::from(read_counter).with(After("count")).data)
+            .unwrap();
+        stage
+            .add(
+                System::from(count)
+                    .with(Label("count"))
+                    .with(After("sum"))
+                    .data,
+            )
+            .unwrap();
+        let result = stage.add(
+            System::from(sum)
+                .with(Label("sum"))
+                .with(After("count"))
+                .data,
+        );
+
+        match result {
+            Err(OrderError::Cycle(cycle)) => {
+                let cycle = cycle
+                    .iter()
+                    .map(|name| name.rsplit("::").next().unwrap())
+                    .collect::<Vec<_>>();
+                assert_eq!(cycle, vec!["count", "sum", "count"]);
+            }
+            _ => panic!("Cycle must be detected"),
+        }
+        assert_eq!(names(&stage), vec!["count", "read_counter"]);
+    }
+
+    #[test]
+    fn ambiguous_labels() {
+        let mut stage = Stage::default();
+        stage
+            .add(System::from(count).with(Label("counter")).data)
+            .unwrap();
+        stage.add(System::from(read_frame).data).unwrap();
+        stage
+            .add(System::from(read_counter).with(After("counter")).data)
+            .unwrap();
+        // only full paths and explicit labels point to systems
+        stage
+            .add(System::from(sum).with(Before("tests::read_frame")).data)
+            .unwrap();
+        assert_eq!(
+            names(&stage),
+            vec!["count", "read_frame", "read_counter", "sum"]
+        );
+
+        let result = stage.add(System::from(sum).with(Label("counter")).data);
+        match result {
+            Err(OrderError::Ambiguous(label, systems)) => {
+                assert_eq!(label, "counter");
+                assert_eq!(systems.len(), 2);
+            }
+            _ => panic!("Ambiguous label must be detected"),
+        }
+        assert_eq!(stage.systems.len(), 4);
+    }
+
+    #[test]
+    fn unknown_labels() {
+        let mut stage = Stage::default();
+        stage
+            .add(System::from(count).with(Before("reader")).data)
+            .unwrap();
+        stage
+            .add(
+                System::from(sum)
+                    .with(After("dotrix_core::application::tests::count"))
+                    .data,
+            )
+            .unwrap();
+        // label may point to a system, that is added later
+        assert_eq!(
+            stage.unknown_labels(),
+            vec![("dotrix_core::application::tests::count", "reader")]
+        );
+
+        stage
+            .add(System::from(read_counter).with(Label("reader")).data)
+            .unwrap();
+        assert!(stage.unknown_labels().is_empty());
+    }
+
+    type LoadResult = Result<Id<Shader>, ImportError>;
+
+    #[derive(Default)]
+    struct Loaded(Arc<Mutex<Vec<LoadResult>>>);
+
+    fn load_startup(mut assets: Mut<Assets>, mut tasks: Mut<Tasks>, loaded: Const<Loaded>) {
+        for path in ["async.wgsl", "missing.wgsl"] {
+            let shader = assets.load_async::<Shader>(path);
+            let loaded = Arc::clone(&loaded.0);
+            tasks.spawn(async move {
+                let result = shader.await;
+                loaded.lock().unwrap().push(result);
+            });
+        }
+    }
+
+    #[test]
+    fn load_async() {
+        let dir = TempDir::new("async");
+        std::fs::write(dir.join("async.wgsl"), "// shader").unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let mut app = Application::new("Async");
+        app.add_service(assets);
+        app.add_service(Tasks::default());
+        app.add_service(Loaded::default());
+        app.add_system(System::from(load_startup).with(RunLevel::Startup));
+
+        let loaded = Arc::clone(&app.service::<Loaded>().0);
+        poll(|| {
+            app.run_frames(1);
+            loaded.lock().unwrap().len() == 2
+        });
+
+        let shader = app.service::<Assets>().find::<Shader>("async").unwrap();
+        let mut results = loaded.lock().unwrap();
+        assert_eq!(results.len(), 2);
+        results.sort_by_key(|result| result.is_err());
+        assert_eq!(results[0].as_ref().ok(), Some(&shader));
+        assert!(matches!(results[1], Err(ImportError::FileRead(_))));
+        assert!(app.service::<Assets>().get(shader).is_some());
     }
 }
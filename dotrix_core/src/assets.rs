@@ -1,28 +1,53 @@
 //! Assets and management service
 pub mod animation;
+pub mod handle;
+pub mod importer;
 mod load_gltf;
+mod load_texture;
 pub mod loader;
+pub mod material;
 pub mod mesh;
+pub mod pack;
+pub mod prefab;
+pub mod preprocessor;
 pub mod resource;
 pub mod shader;
 pub mod skin;
+mod storage;
 pub mod texture;
+mod watcher;
 
 pub use animation::Animation;
+pub use handle::Handle;
+pub use importer::{AssetImporter, CustomAsset, Files, Sender};
 pub use loader::*;
+pub use material::Material;
 pub use mesh::*;
+pub use pack::{Pack, PackWriter};
+pub use prefab::Prefab;
+pub use preprocessor::{Preprocessor, PreprocessorError};
 pub use resource::*;
 pub use shader::Shader;
 pub use skin::Skin;
 pub use texture::*;
 
 use std::{
+    any::TypeId,
     collections::{hash_map, HashMap, HashSet},
-    sync::{mpsc, Arc, Mutex},
+    future::Future,
+    sync::{mpsc, Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
     vec::Vec,
 };
 
+use futures::channel::oneshot;
+use log::{error, info};
+
 use crate::{ecs::Mut, id::Id, Renderer};
+use importer::{
+    DdsImporter, ExrImporter, GltfImporter, HdrImporter, ImageImporter, Ktx2Importer, WgslImporter,
+};
+use storage::Storage;
+use watcher::Watcher;
 
 const THREADS_COUNT: usize = 4;
 
@@ -36,21 +61,31 @@ const THREADS_COUNT: usize = 4;
 /// loaded at the same time. Also bigger assets may take longer time to be loaded.
 ///
 /// There is a way to aquire asset [`Id`] immediately without awaiting using [`Assets::register`]
-/// method.
+/// method. Import progress of a file can be tracked by [`Resource::status`], that also keeps
+/// names of assets the file produced, once it is imported.
 pub struct Assets {
     registry: HashMap<String, u64>,
     resources: HashMap<Id<Resource>, Resource>,
     animations: HashMap<Id<Animation>, Animation>,
     textures: HashMap<Id<Texture>, Texture>,
+    materials: HashMap<Id<Material>, Material>,
     meshes: HashMap<Id<Mesh>, Mesh>,
+    prefabs: HashMap<Id<Prefab>, Prefab>,
     shaders: HashMap<Id<Shader>, Shader>,
     skins: HashMap<Id<Skin>, Skin>,
+    custom: HashMap<TypeId, Box<dyn Storage>>,
+    handles: HashMap<(TypeId, u64), Tracked>,
+    waiters: HashMap<Id<Resource>, Vec<Waiter>>,
+    importers: Importers,
+    packs: Packs,
+    preprocessor: Arc<RwLock<Preprocessor>>,
     loaders: Vec<Loader>,
     sender: mpsc::Sender<Request>,
     receiver: mpsc::Receiver<Response>,
     id_generator: u64,
     removed_shaders: HashSet<Id<Shader>>,
     hot_reload: bool,
+    watcher: Watcher,
     root: std::path::PathBuf,
 }
 
@@ -62,6 +97,9 @@ impl Assets {
         let (thread_tx, receiver) = mpsc::channel();
         let thread_rx = Arc::new(Mutex::new(thread_rx));
         let thread_tx = Arc::new(Mutex::new(thread_tx));
+        let importers: Importers = Arc::new(RwLock::new(HashMap::new()));
+        let packs: Packs = Arc::new(RwLock::new(Vec::new()));
+        let preprocessor = Arc::new(RwLock::new(Preprocessor::new()));
         let mut loaders = Vec::with_capacity(threads_count);
 
         for id in 0..threads_count {
@@ -69,29 +107,80 @@ impl Assets {
                 id,
                 Arc::clone(&thread_rx),
                 Arc::clone(&thread_tx),
+                Arc::clone(&importers),
+                Arc::clone(&packs),
             ));
         }
 
         let root = std::env::current_dir().expect("Current working directory must be accessible");
 
-        Self {
+        let mut assets = Self {
             registry: HashMap::new(),
             resources: HashMap::new(),
             animations: HashMap::new(),
             textures: HashMap::new(),
+            materials: HashMap::new(),
             meshes: HashMap::new(),
+            prefabs: HashMap::new(),
             shaders: HashMap::new(),
             skins: HashMap::new(),
+            custom: HashMap::new(),
+            handles: HashMap::new(),
+            waiters: HashMap::new(),
+            importers,
+            packs,
+            preprocessor: Arc::clone(&preprocessor),
             loaders,
             sender,
             receiver,
             id_generator: 1,
             removed_shaders: HashSet::new(),
             hot_reload: true,
+            watcher: Watcher::new(true),
             root,
+        };
+
+        assets.add_importer(ImageImporter);
+        assets.add_importer(WgslImporter::new(preprocessor));
+        assets.add_importer(GltfImporter);
+        assets.add_importer(Ktx2Importer);
+        assets.add_importer(DdsImporter);
+        assets.add_importer(HdrImporter);
+        assets.add_importer(ExrImporter);
+        assets
+    }
+
+    /// Registers an [`AssetImporter`] for its file extensions
+    ///
+    /// If an extension was already served by another importer, the new one replaces it.
+    pub fn add_importer<I: AssetImporter>(&mut self, importer: I) {
+        let importer: Arc<dyn AssetImporter> = Arc::new(importer);
+        let mut importers = self.importers.write().unwrap();
+        for extension in importer.extensions() {
+            importers.insert(String::from(*extension), Arc::clone(&importer));
         }
     }
 
+    /// Returns the [`Preprocessor`] of imported WGSL shaders
+    pub fn preprocessor(&self) -> RwLockReadGuard<'_, Preprocessor> {
+        self.preprocessor.read().unwrap()
+    }
+
+    /// Returns the [`Preprocessor`] of imported WGSL shaders to register modules and definitions
+    ///
+    /// Changes apply to shaders imported after them, preprocessing errors are reported by
+    /// [`Resource::status`].
+    pub fn preprocessor_mut(&mut self) -> RwLockWriteGuard<'_, Preprocessor> {
+        self.preprocessor.write().unwrap()
+    }
+
+    /// Registers a [`CustomAsset`] type, so assets of it could be stored
+    pub fn add_type<T: CustomAsset>(&mut self) {
+        self.custom
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(HashMap::<Id<T>, T>::new()));
+    }
+
     /// Set assets root directory
     pub fn set_root(&mut self, root: std::path::PathBuf) {
         self.root = root;
@@ -102,28 +191,149 @@ impl Assets {
         self.root.as_path()
     }
 
+    /// Mounts an asset pack to the assets root directory
+    ///
+    /// Files of mounted packs are imported as if they were stored in the root directory. Packs
+    /// are looked up before the filesystem, in order they were mounted.
+    pub fn mount(&mut self, path: std::path::PathBuf) -> std::io::Result<()> {
+        let pack = Pack::open(path.as_path(), self.root.as_path())?;
+        self.packs.write().unwrap().push(Arc::new(pack));
+        Ok(())
+    }
+
     /// Imports an asset file by its relative path and returns [`Id`] of the [`Resource`]
     pub fn import(&mut self, path_str: &str) -> Id<Resource> {
         let path = self.root.as_path().join(path_str);
         self.import_from(path)
     }
 
+    /// Imports an asset file by its relative path with custom settings and returns [`Id`] of
+    /// the [`Resource`]
+    ///
+    /// ```no_run
+    /// use dotrix_core::{assets::ImportSettings, Assets};
+    ///
+    /// let mut assets = Assets::new();
+    /// // UI textures are drawn in their original size and need no mip levels
+    /// assets.import_with("assets/button.png", ImportSettings::default().mipmaps(false));
+    /// ```
+    pub fn import_with(&mut self, path_str: &str, settings: ImportSettings) -> Id<Resource> {
+        let path = self.root.as_path().join(path_str);
+        self.import_from_with(path, settings)
+    }
+
     /// Imports an asset file from specified absolute or relative path and returns [`Id`] of the
     /// [`Resource`]
     pub fn import_from(&mut self, path: std::path::PathBuf) -> Id<Resource> {
+        self.import_from_with(path, ImportSettings::default())
+    }
+
+    /// Imports an asset file from specified absolute or relative path with custom settings and
+    /// returns [`Id`] of the [`Resource`]
+    pub fn import_from_with(
+        &mut self,
+        path: std::path::PathBuf,
+        settings: ImportSettings,
+    ) -> Id<Resource> {
         let name = path
             .file_stem()
             .map(|n| n.to_str().unwrap())
             .unwrap()
             .to_string();
-        let resource = Resource::new(name.clone(), path.as_path().display().to_string());
+        let resource = Resource::new(name.clone(), path.as_path().display().to_string())
+            .with_settings(settings);
         let id = self.store_as::<Resource>(resource, &name);
+        self.watcher.watch(path.as_path(), id);
 
-        let task = Task { path, name };
+        let task = Task {
+            path,
+            name,
+            resource: id,
+            settings,
+        };
         self.sender.send(Request::Import(task)).unwrap();
         id
     }
 
+    /// Imports an asset file by its relative path and returns a future of the asset [`Id`]
+    ///
+    /// The future resolves, once the import is finished and assets are fetched. If the file
+    /// contains several assets of the type, [`Id`] of the first one is returned. Futures can be
+    /// awaited inside of [`crate::Tasks`].
+    pub fn load_async<T>(
+        &mut self,
+        path_str: &str,
+    ) -> impl Future<Output = Result<Id<T>, ImportError>>
+    where
+        Self: AssetMapGetter<T>,
+        T: 'static,
+    {
+        let resource = self.import(path_str);
+        let (sender, receiver) = oneshot::channel();
+        let waiter: Waiter = Box::new(move |assets, resource| {
+            let result = match resource.status() {
+                Status::Failed(error) => Err(error.duplicate()),
+                _ => resource
+                    .assets()
+                    .iter()
+                    .filter_map(|name| assets.find::<T>(name))
+                    .find(|id| assets.get(*id).is_some())
+                    .and_then(|id| id.as_u64())
+                    .ok_or(ImportError::MissingAsset(std::any::type_name::<T>())),
+            };
+            // raw id is sent, so the future stays `Send` for any asset type, and it could be
+            // dropped already
+            sender.send(result).ok();
+        });
+        self.waiters.entry(resource).or_default().push(waiter);
+
+        async move {
+            receiver
+                .await
+                .unwrap_or(Err(ImportError::Cancelled))
+                .map(Id::new)
+        }
+    }
+
+    /// Returns true if import of all the resources was finished, successfully or not
+    ///
+    /// Unknown resources are considered as ready. The method can be used to wait for a group
+    /// of imports, for example, on a loading screen:
+    ///
+    /// ```no_run
+    /// use dotrix_core::{assets::Resource, ecs::{Const, Mut}, Assets, Id, State};
+    ///
+    /// struct Loading {
+    ///     resources: Vec<Id<Resource>>,
+    /// }
+    ///
+    /// fn loading(mut state: Mut<State>, assets: Const<Assets>) {
+    ///     let ready = state
+    ///         .get::<Loading>()
+    ///         .map(|loading| assets.is_ready(&loading.resources))
+    ///         .unwrap_or(false);
+    ///     if ready {
+    ///         state.pop_any();
+    ///     }
+    /// }
+    /// ```
+    pub fn is_ready(&self, resources: &[Id<Resource>]) -> bool {
+        resources.iter().all(|id| {
+            self.resources
+                .get(id)
+                .map(|resource| resource.is_ready())
+                .unwrap_or(true)
+        })
+    }
+
+    /// Returns number of resources that are still being imported
+    pub fn pending(&self) -> usize {
+        self.resources
+            .values()
+            .filter(|resource| !resource.is_ready())
+            .count()
+    }
+
     /// Associates an asset name with [`Id`] and returns it
     ///
     /// If name was already used, then no changes will be done and already associated [`Id`] will
@@ -183,12 +393,24 @@ impl Assets {
         self.registry.get(&name.to_string()).map(|id| Id::new(*id))
     }
 
+    /// Searches for a name of the asset by its [`Id`]
+    pub fn name<T>(&self, handle: Id<T>) -> Option<&str>
+    where
+        Self: AssetMapGetter<T>,
+    {
+        let raw_id = handle.as_u64()?;
+        self.registry
+            .iter()
+            .find(|(_, id)| **id == raw_id)
+            .map(|(name, _)| name.as_str())
+    }
+
     /// Searches an asset by its [`Id`] and returns it by a reference if the asset exists
     pub fn get<T>(&self, handle: Id<T>) -> Option<&T>
     where
         Self: AssetMapGetter<T>,
     {
-        self.map().get(&handle)
+        self.map()?.get(&handle)
     }
 
     /// Searches an asset by its [`Id`] and returns it by a mutual reference if the asset exists
@@ -199,6 +421,62 @@ impl Assets {
         self.map_mut().get_mut(&handle)
     }
 
+    /// Returns reference counting [`Handle`] of an asset or `None` if the [`Id`] is not numeric
+    ///
+    /// Once all handles of the asset are dropped, the asset is removed by the `assets::release`
+    /// system. Assets that are still being imported are kept until they are stored or import of
+    /// their [`Resource`] fails.
+    pub fn handle<T>(&mut self, id: Id<T>) -> Option<Handle<T>>
+    where
+        T: 'static,
+        Self: AssetMapGetter<T>,
+    {
+        let raw_id = id.as_u64()?;
+        let pending = self.get(id).is_none();
+        let tracked = self
+            .handles
+            .entry((TypeId::of::<T>(), raw_id))
+            .or_insert_with(|| Tracked {
+                counter: Arc::new(()),
+                pending,
+                contains: contains_tracked::<T>,
+                remove: remove_tracked::<T>,
+            });
+        Some(Handle::new(id, Arc::clone(&tracked.counter)))
+    }
+
+    /// Removes assets, that have no more handles, and returns number of removed assets
+    pub fn release_unreferenced(&mut self) -> usize {
+        let stored = self
+            .handles
+            .iter()
+            .filter(|(&(_, raw_id), tracked)| tracked.pending && (tracked.contains)(self, raw_id))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        for key in stored {
+            if let Some(tracked) = self.handles.get_mut(&key) {
+                tracked.pending = false;
+            }
+        }
+
+        let unreferenced = self
+            .handles
+            .iter()
+            .filter(|(_, tracked)| !tracked.pending && Arc::strong_count(&tracked.counter) == 1)
+            .map(|(key, tracked)| (*key, tracked.remove))
+            .collect::<Vec<_>>();
+
+        let mut released = 0;
+        for (key, remove) in unreferenced {
+            // the asset could be already removed by `Assets::remove`
+            if remove(self, key.1) {
+                released += 1;
+            }
+            self.handles.remove(&key);
+        }
+        released
+    }
+
     /// Removes an asset from the Service and returns it if the asset exists
     pub fn remove<T>(&mut self, handle: Id<T>) -> Option<T>
     where
@@ -213,7 +491,8 @@ impl Assets {
     where
         Self: AssetMapGetter<T>,
     {
-        self.map().iter()
+        // storage of unregistered custom assets is created on demand
+        self.map_mut().iter()
     }
 
     /// Returns mutable iterator over assets by its type
@@ -231,14 +510,24 @@ impl Assets {
     }
 
     pub(crate) fn fetch(&mut self) {
+        if self.hot_reload {
+            self.reload();
+        }
+
         while let Ok(response) = self.receiver.try_recv() {
             match response {
                 Response::Animation(animation) => {
                     self.store_as(*animation.asset, &animation.name);
                 }
+                Response::Material(material) => {
+                    self.store_as(*material.asset, &material.name);
+                }
                 Response::Mesh(mesh) => {
                     self.store_as(*mesh.asset, &mesh.name);
                 }
+                Response::Prefab(prefab) => {
+                    self.store_as(*prefab.asset, &prefab.name);
+                }
                 Response::Shader(shader) => {
                     self.store_as(*shader.asset, &shader.name);
                 }
@@ -246,23 +535,136 @@ impl Assets {
                     self.store_as(*skin.asset, &skin.name);
                 }
                 Response::Texture(texture) => {
-                    self.store_as(*texture.asset, &texture.name);
+                    let mut asset = *texture.asset;
+                    let replaced = self
+                        .find::<Texture>(&texture.name)
+                        .and_then(|id| self.textures.get_mut(&id));
+                    if let Some(replaced) = replaced {
+                        // GPU texture of the replaced one gets all levels rewritten on load
+                        std::mem::swap(&mut asset.buffer, &mut replaced.buffer);
+                        asset.changed = true;
+                    }
+                    self.store_as(asset, &texture.name);
+                }
+                Response::Custom(custom) => {
+                    let type_id = (*custom.asset).type_id();
+                    let raw_id = self.next_id();
+                    let raw_id = *self.registry.entry(custom.name.clone()).or_insert(raw_id);
+                    if let Some(storage) = self.custom.get_mut(&type_id) {
+                        storage.insert(raw_id, custom.asset);
+                    } else {
+                        error!("Asset `{}` is of unregistered type", custom.name);
+                    }
+                }
+                Response::Imported(imported) => {
+                    if let Some(resource) = self.resources.get_mut(&imported.resource) {
+                        resource.imported(imported.assets, imported.result);
+                        if let Status::Failed(_) = resource.status() {
+                            let name = resource.name().clone();
+                            self.abandon_pending(&name);
+                        }
+                    }
+                    if let Some(waiters) = self.waiters.remove(&imported.resource) {
+                        if let Some(resource) = self.resources.get(&imported.resource) {
+                            for waiter in waiters {
+                                waiter(self, resource);
+                            }
+                        }
+                    }
                 }
             };
         }
     }
 
-    /// Enable/Disable hot reload of certain assets. If this is
-    /// disabled certain assets like `Shaders` need to be
+    /// Stops waiting for assets of the failed resource, so their handles are released as usual
+    ///
+    /// Assets of the resource are recognized by names, that start with the resource name.
+    fn abandon_pending(&mut self, resource: &str) {
+        let prefixes = [format!("{}::", resource), format!("{}.", resource)];
+        let raw_ids = self
+            .registry
+            .iter()
+            .filter(|(name, _)| {
+                name.as_str() == resource || prefixes.iter().any(|prefix| name.starts_with(prefix))
+            })
+            .map(|(_, raw_id)| *raw_id)
+            .collect::<HashSet<_>>();
+        for ((_, raw_id), tracked) in self.handles.iter_mut() {
+            if raw_ids.contains(raw_id) {
+                tracked.pending = false;
+            }
+        }
+    }
+
+    /// Re-imports resources, which files were changed
+    ///
+    /// Import of a resource, that is still pending, is postponed until it is finished.
+    fn reload(&mut self) {
+        let changes = self.watcher.changes();
+        for id in changes {
+            if let Some(resource) = self.resources.get_mut(&id) {
+                if resource.is_ready() {
+                    info!("reloading `{}`", resource.path());
+                    resource.pending();
+                    let task = Task {
+                        path: std::path::PathBuf::from(resource.path()),
+                        name: resource.name().clone(),
+                        resource: id,
+                        settings: resource.settings(),
+                    };
+                    self.sender.send(Request::Import(task)).unwrap();
+                } else {
+                    self.watcher.postpone(id);
+                }
+            }
+        }
+    }
+
+    /// Enable/Disable hot reload of assets
+    ///
+    /// When enabled, files of imported resources are watched and re-imported on change. Assets
+    /// are replaced keeping their [`Id`]s, pipelines of changed shaders and textures are
+    /// recreated. If this is disabled certain assets like `Shaders` need to be
     /// cleaned up manually with `renderer.drop_pipeline`
     pub fn hot_reload_enable(&mut self, enable: bool) {
         self.hot_reload = enable;
+        self.watcher.enable(enable);
     }
 }
 
-/// Reload assets and cleanup any assets that need some post process
-/// after `assets.remove`
+/// Resolves a future of [`Assets::load_async`], once the resource is imported
+type Waiter = Box<dyn FnOnce(&Assets, &Resource) + Send>;
+
+/// Asset, tracked by handles
+struct Tracked {
+    counter: Arc<()>,
+    /// The asset was not stored yet
+    pending: bool,
+    contains: fn(&Assets, u64) -> bool,
+    remove: fn(&mut Assets, u64) -> bool,
+}
+
+/// Returns true if tracked asset exists
+fn contains_tracked<T>(assets: &Assets, raw_id: u64) -> bool
+where
+    Assets: AssetMapGetter<T>,
+{
+    assets.get::<T>(Id::new(raw_id)).is_some()
+}
+
+/// Removes tracked asset and returns true if it existed
+fn remove_tracked<T>(assets: &mut Assets, raw_id: u64) -> bool
+where
+    Assets: AssetMapGetter<T>,
+{
+    assets.remove::<T>(Id::new(raw_id)).is_some()
+}
+
+/// Release unreferenced assets, reload assets and cleanup any assets that need some post
+/// process after `assets.remove`
 pub fn release(mut assets: Mut<Assets>, mut renderer: Mut<Renderer>) {
+    assets.release_unreferenced();
+
     if !assets.hot_reload {
         return;
     }
@@ -286,8 +688,8 @@ pub fn release(mut assets: Mut<Assets>, mut renderer: Mut<Renderer>) {
 
 /// Asset map getting trait
 pub trait AssetMapGetter<T> {
-    /// Returns HashMap reference for selected asset type
-    fn map(&self) -> &HashMap<Id<T>, T>;
+    /// Returns HashMap reference for selected asset type, if the type is registered
+    fn map(&self) -> Option<&HashMap<Id<T>, T>>;
     /// Returns mutable HashMap reference for selected asset type
     fn map_mut(&mut self) -> &mut HashMap<Id<T>, T>;
 
@@ -304,8 +706,8 @@ pub trait AssetMapGetter<T> {
 }
 
 impl AssetMapGetter<Animation> for Assets {
-    fn map(&self) -> &HashMap<Id<Animation>, Animation> {
-        &self.animations
+    fn map(&self) -> Option<&HashMap<Id<Animation>, Animation>> {
+        Some(&self.animations)
     }
 
     fn map_mut(&mut self) -> &mut HashMap<Id<Animation>, Animation> {
@@ -314,8 +716,8 @@ impl AssetMapGetter<Animation> for Assets {
 }
 
 impl AssetMapGetter<Texture> for Assets {
-    fn map(&self) -> &HashMap<Id<Texture>, Texture> {
-        &self.textures
+    fn map(&self) -> Option<&HashMap<Id<Texture>, Texture>> {
+        Some(&self.textures)
     }
 
     fn map_mut(&mut self) -> &mut HashMap<Id<Texture>, Texture> {
@@ -323,9 +725,19 @@ impl AssetMapGetter<Texture> for Assets {
     }
 }
 
+impl AssetMapGetter<Material> for Assets {
+    fn map(&self) -> Option<&HashMap<Id<Material>, Material>> {
+        Some(&self.materials)
+    }
+
+    fn map_mut(&mut self) -> &mut HashMap<Id<Material>, Material> {
+        &mut self.materials
+    }
+}
+
 impl AssetMapGetter<Mesh> for Assets {
-    fn map(&self) -> &HashMap<Id<Mesh>, Mesh> {
-        &self.meshes
+    fn map(&self) -> Option<&HashMap<Id<Mesh>, Mesh>> {
+        Some(&self.meshes)
     }
 
     fn map_mut(&mut self) -> &mut HashMap<Id<Mesh>, Mesh> {
@@ -333,9 +745,19 @@ impl AssetMapGetter<Mesh> for Assets {
     }
 }
 
+impl AssetMapGetter<Prefab> for Assets {
+    fn map(&self) -> Option<&HashMap<Id<Prefab>, Prefab>> {
+        Some(&self.prefabs)
+    }
+
+    fn map_mut(&mut self) -> &mut HashMap<Id<Prefab>, Prefab> {
+        &mut self.prefabs
+    }
+}
+
 impl AssetMapGetter<Skin> for Assets {
-    fn map(&self) -> &HashMap<Id<Skin>, Skin> {
-        &self.skins
+    fn map(&self) -> Option<&HashMap<Id<Skin>, Skin>> {
+        Some(&self.skins)
     }
 
     fn map_mut(&mut self) -> &mut HashMap<Id<Skin>, Skin> {
@@ -344,8 +766,8 @@ impl AssetMapGetter<Skin> for Assets {
 }
 
 impl AssetMapGetter<Resource> for Assets {
-    fn map(&self) -> &HashMap<Id<Resource>, Resource> {
-        &self.resources
+    fn map(&self) -> Option<&HashMap<Id<Resource>, Resource>> {
+        Some(&self.resources)
     }
 
     fn map_mut(&mut self) -> &mut HashMap<Id<Resource>, Resource> {
@@ -354,8 +776,8 @@ impl AssetMapGetter<Resource> for Assets {
 }
 
 impl AssetMapGetter<Shader> for Assets {
-    fn map(&self) -> &HashMap<Id<Shader>, Shader> {
-        &self.shaders
+    fn map(&self) -> Option<&HashMap<Id<Shader>, Shader>> {
+        Some(&self.shaders)
     }
 
     fn map_mut(&mut self) -> &mut HashMap<Id<Shader>, Shader> {
@@ -370,6 +792,24 @@ impl AssetMapGetter<Shader> for Assets {
     }
 }
 
+impl<T: CustomAsset> AssetMapGetter<T> for Assets {
+    fn map(&self) -> Option<&HashMap<Id<T>, T>> {
+        self.custom
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_ref().downcast_ref())
+    }
+
+    fn map_mut(&mut self) -> &mut HashMap<Id<T>, T> {
+        self.add_type::<T>();
+        self.custom
+            .get_mut(&TypeId::of::<T>())
+            .unwrap()
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+}
+
 impl Drop for Assets {
     fn drop(&mut self) {
         for _ in &self.loaders {
@@ -389,3 +829,327 @@ impl Default for Assets {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fetch_until, TempDir};
+
+    fn wait(assets: &mut Assets, resources: &[Id<Resource>]) {
+        fetch_until(assets, |assets| assets.is_ready(resources));
+    }
+
+    #[test]
+    fn import_status() {
+        let dir = TempDir::new("import");
+        std::fs::write(dir.join("simple.wgsl"), "// shader").unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let loaded = assets.import("simple.wgsl");
+        let failed = assets.import("missing.wgsl");
+        assert_eq!(assets.pending(), 2);
+        assert!(matches!(
+            assets.get(loaded).unwrap().status(),
+            Status::Pending
+        ));
+
+        wait(&mut assets, &[loaded, failed]);
+        assert_eq!(assets.pending(), 0);
+
+        let resource = assets.get(loaded).unwrap();
+        assert!(matches!(resource.status(), Status::Loaded));
+        assert_eq!(resource.assets(), &[String::from("simple")]);
+        assert!(assets.find::<Shader>("simple").is_some());
+
+        let resource = assets.get(failed).unwrap();
+        assert!(matches!(
+            resource.status(),
+            Status::Failed(ImportError::FileRead(_))
+        ));
+        assert!(resource.assets().is_empty());
+    }
+
+    #[test]
+    fn cancelled_load() {
+        let dir = TempDir::new("cancel");
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let shader = assets.load_async::<Shader>("missing.wgsl");
+        drop(assets);
+
+        let result = futures::executor::block_on(shader);
+        assert!(matches!(result, Err(ImportError::Cancelled)));
+    }
+
+    #[test]
+    fn preprocessed_import() {
+        let dir = TempDir::new("preprocess");
+        std::fs::write(dir.join("lit.wgsl"), "#include \"light\"\nlit();").unwrap();
+        std::fs::write(dir.join("broken.wgsl"), "\n#include \"shadows\"").unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        assets.preprocessor_mut().add_module("light", "fn lit() {}");
+        let lit = assets.import("lit.wgsl");
+        let broken = assets.import("broken.wgsl");
+        wait(&mut assets, &[lit, broken]);
+
+        let shader = assets.find::<Shader>("lit").unwrap();
+        assert_eq!(assets.get(shader).unwrap().code, "fn lit() {}\nlit();\n");
+
+        match assets.get(broken).unwrap().status() {
+            Status::Failed(ImportError::Preprocessor(err)) => assert_eq!(err.line, 2),
+            _ => panic!("Preprocessor error must be reported"),
+        }
+        let broken = assets.find::<Shader>("broken");
+        assert!(broken.and_then(|shader| assets.get(shader)).is_none());
+    }
+
+    #[test]
+    fn hot_reload() {
+        let dir = TempDir::new("reload");
+        let path = dir.join("reloaded.wgsl");
+        std::fs::write(&path, "// version 1").unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let resource = assets.import("reloaded.wgsl");
+        wait(&mut assets, &[resource]);
+
+        let shader = assets.find::<Shader>("reloaded").unwrap();
+        assert_eq!(assets.get(shader).unwrap().code, "// version 1\n");
+
+        std::fs::write(&path, "// version 2").unwrap();
+        fetch_until(&mut assets, |assets| {
+            assets.get(shader).unwrap().code == "// version 2\n" && assets.is_ready(&[resource])
+        });
+
+        assert_eq!(assets.find::<Shader>("reloaded"), Some(shader));
+        assert_eq!(assets.get(shader).unwrap().code, "// version 2\n");
+        assert!(matches!(
+            assets.get(resource).unwrap().status(),
+            Status::Loaded
+        ));
+    }
+
+    #[test]
+    fn texture_reload() {
+        let dir = TempDir::new("texture");
+        let path = dir.join("reloaded.png");
+        let save = |pixel| {
+            image::RgbaImage::from_pixel(2, 2, image::Rgba(pixel))
+                .save(&path)
+                .unwrap()
+        };
+        save([255, 0, 0, 255]);
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let resource = assets.import("reloaded.png");
+        wait(&mut assets, &[resource]);
+
+        // mip levels are generated by the importer
+        let texture = assets.find::<Texture>("reloaded").unwrap();
+        assert_eq!(
+            assets.get(texture).unwrap().levels,
+            vec![vec![255, 0, 0, 255]]
+        );
+        assert!(!assets.get(texture).unwrap().changed);
+
+        save([0, 255, 0, 255]);
+        fetch_until(&mut assets, |assets| {
+            assets.get(texture).unwrap().data[0] == 0 && assets.is_ready(&[resource])
+        });
+
+        // GPU texture of the replaced asset is kept to rewrite all of its levels
+        let reloaded = assets.get(texture).unwrap();
+        assert_eq!(reloaded.levels, vec![vec![0, 255, 0, 255]]);
+        assert!(reloaded.changed);
+    }
+
+    #[test]
+    fn import_without_mipmaps() {
+        let dir = TempDir::new("mipmaps");
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]))
+            .save(dir.join("button.png"))
+            .unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let settings = ImportSettings::default().mipmaps(false);
+        let resource = assets.import_with("button.png", settings);
+        wait(&mut assets, &[resource]);
+
+        // settings are kept to be applied on reload
+        assert_eq!(assets.get(resource).unwrap().settings(), settings);
+        let texture = assets.find::<Texture>("button").unwrap();
+        assert!(assets.get(texture).unwrap().levels.is_empty());
+    }
+
+    struct Level(Vec<u8>);
+
+    impl CustomAsset for Level {}
+
+    struct LevelImporter;
+
+    impl AssetImporter for LevelImporter {
+        fn extensions(&self) -> &[&str] {
+            &["level"]
+        }
+
+        fn import(
+            &self,
+            sender: &Sender,
+            name: String,
+            data: Vec<u8>,
+            _files: &Files,
+        ) -> Result<(), ImportError> {
+            if data.is_empty() {
+                return Err(ImportError::Custom("empty level".into()));
+            }
+            sender
+                .lock()
+                .unwrap()
+                .send(Response::custom(name, Level(data)))
+                .unwrap();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unregistered_custom_asset() {
+        let mut assets = Assets::new();
+        assert!(assets.get(Id::<Level>::new(1)).is_none());
+        assert!(assets.find::<Level>("level").is_none());
+        assert!(assets.name(Id::<Level>::new(1)).is_none());
+        assert_eq!(assets.iter::<Level>().count(), 0);
+
+        let level = assets.store_as(Level(vec![1]), "level");
+        assert_eq!(assets.get(level).unwrap().0, vec![1]);
+    }
+
+    #[test]
+    fn custom_importer() {
+        let dir = TempDir::new("importer");
+        std::fs::write(dir.join("first.level"), [1, 2, 3]).unwrap();
+        std::fs::write(dir.join("empty.level"), []).unwrap();
+        std::fs::write(dir.join("unknown.map"), [1]).unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        assets.add_type::<Level>();
+        assets.add_importer(LevelImporter);
+
+        let first = assets.import("first.level");
+        let empty = assets.import("empty.level");
+        let unknown = assets.import("unknown.map");
+        wait(&mut assets, &[first, empty, unknown]);
+
+        assert!(matches!(
+            assets.get(first).unwrap().status(),
+            Status::Loaded
+        ));
+        let level = assets.find::<Level>("first").unwrap();
+        assert_eq!(assets.get(level).unwrap().0, vec![1, 2, 3]);
+
+        assert!(matches!(
+            assets.get(empty).unwrap().status(),
+            Status::Failed(ImportError::Custom(_))
+        ));
+        assert!(matches!(
+            assets.get(unknown).unwrap().status(),
+            Status::Failed(ImportError::NotImplemented("extension", Some(_)))
+        ));
+    }
+
+    #[test]
+    fn import_from_pack() {
+        let dir = TempDir::new("mount");
+        std::fs::write(dir.join("shader.wgsl"), "// filesystem").unwrap();
+
+        let mut pack = PackWriter::new();
+        pack.add("shaders/shader.wgsl", Vec::from("// pack"));
+        let path = dir.join("assets.pack");
+        pack.write(&mut std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        assets.mount(path).unwrap();
+
+        let packed = assets.import("shaders/shader.wgsl");
+        wait(&mut assets, &[packed]);
+        let shader = assets.find::<Shader>("shader").unwrap();
+        assert_eq!(assets.get(shader).unwrap().code, "// pack\n");
+
+        let file = assets.import("shader.wgsl");
+        wait(&mut assets, &[file]);
+        assert_eq!(assets.get(shader).unwrap().code, "// filesystem\n");
+    }
+
+    #[test]
+    fn handles() {
+        let mut assets = Assets::new();
+        let mesh = assets.store(Mesh::default());
+        let handle = assets.handle(mesh).unwrap();
+        let copy = handle.clone();
+        assert_eq!(copy.id(), mesh);
+        assert_eq!(handle.references(), 2);
+
+        drop(handle);
+        assert_eq!(assets.release_unreferenced(), 0);
+        assert!(assets.get(mesh).is_some());
+
+        drop(copy);
+        assert_eq!(assets.release_unreferenced(), 1);
+        assert!(assets.get(mesh).is_none());
+
+        // asset, that is not stored yet, is released once it arrives
+        let texture = assets.register::<Texture>("texture");
+        drop(assets.handle(texture));
+        assert_eq!(assets.release_unreferenced(), 0);
+        assets.store_as(Texture::default(), "texture");
+        assert_eq!(assets.release_unreferenced(), 1);
+        assert!(assets.get(texture).is_none());
+
+        // asset, removed while it has handles, is not tracked anymore
+        let mesh = assets.store(Mesh::default());
+        let handle = assets.handle(mesh).unwrap();
+        assets.remove(mesh);
+        drop(handle);
+        assert_eq!(assets.release_unreferenced(), 0);
+        assert!(assets.handles.is_empty());
+
+        // assets of different types with equal raw ids are tracked separately
+        let mesh = assets.store(Mesh::default());
+        let mesh_handle = assets.handle(mesh).unwrap();
+        let shader = Id::<Shader>::new(mesh.unwrap_u64());
+        let shader_handle = assets.handle(shader).unwrap();
+        assert_eq!(mesh_handle.references(), 1);
+        assert_eq!(shader_handle.references(), 1);
+
+        assert!(assets
+            .handle(Id::<Mesh>::from(TypeId::of::<Mesh>()))
+            .is_none());
+    }
+
+    #[test]
+    fn handles_of_failed_import() {
+        let dir = TempDir::new("handles");
+        let mut assets = Assets::new();
+        assets.set_root(dir.path().to_path_buf());
+        let resource = assets.import("missing.png");
+        let texture = assets.register::<Texture>("missing");
+        let handle = assets.handle(texture).unwrap();
+        wait(&mut assets, &[resource]);
+
+        // referenced asset could still be stored manually
+        assert_eq!(assets.release_unreferenced(), 0);
+        assert_eq!(assets.handles.len(), 1);
+
+        drop(handle);
+        assert_eq!(assets.release_unreferenced(), 0);
+        assert!(assets.handles.is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+//! Strong asset handle
+use std::{fmt::Debug, sync::Arc};
+
+use crate::id::Id;
+
+/// Reference counting handle of an asset
+///
+/// Unlike [`Id`], the handle keeps the asset alive. Once all handles of an asset are dropped,
+/// the asset is removed from [`super::Assets`] and its CPU and GPU memory is freed by the
+/// `assets::release` system. Handles can be obtained with [`super::Assets::handle`] and stored
+/// in components instead of [`Id`]s.
+pub struct Handle<T> {
+    id: Id<T>,
+    counter: Arc<()>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(id: Id<T>, counter: Arc<()>) -> Self {
+        Self { id, counter }
+    }
+
+    /// Returns [`Id`] of the asset
+    pub fn id(&self) -> Id<T> {
+        self.id
+    }
+
+    /// Returns number of handles of the asset
+    pub fn references(&self) -> usize {
+        // one reference is kept by `Assets`
+        Arc::strong_count(&self.counter) - 1
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            counter: Arc::clone(&self.counter),
+        }
+    }
+}
+
+impl<T> Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle({:?})", self.id)
+    }
+}
+
+impl<T> From<&Handle<T>> for Id<T> {
+    fn from(handle: &Handle<T>) -> Self {
+        handle.id
+    }
+}
@@ -0,0 +1,303 @@
+//! Pluggable asset importers
+use std::{
+    any::Any,
+    io,
+    path::Path,
+    sync::{mpsc, Arc, Mutex, RwLock},
+};
+
+use super::{
+    load_gltf::load_gltf,
+    load_texture::{load_dds, load_exr, load_hdr, load_ktx2},
+    loader::{load_image, load_wgsl, Asset, ImportError, Response},
+    pack::Pack,
+    preprocessor::Preprocessor,
+    resource::ImportSettings,
+};
+
+/// Channel to send imported assets to [`super::Assets`]
+pub type Sender = Arc<Mutex<mpsc::Sender<Response>>>;
+
+/// Access to files of an imported resource
+///
+/// Files are read from mounted packs first and from the filesystem then.
+pub struct Files<'a> {
+    path: &'a Path,
+    packs: &'a [Arc<Pack>],
+    settings: ImportSettings,
+}
+
+impl<'a> Files<'a> {
+    /// Constructs new [`Files`] for the resource path and mounted packs
+    pub fn new(path: &'a Path, packs: &'a [Arc<Pack>]) -> Self {
+        Self {
+            path,
+            packs,
+            settings: ImportSettings::default(),
+        }
+    }
+
+    /// Sets import settings of the resource
+    #[must_use]
+    pub fn with_settings(mut self, settings: ImportSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Returns path of the imported resource
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    /// Returns import settings of the resource, that importers should respect
+    pub fn settings(&self) -> ImportSettings {
+        self.settings
+    }
+
+    /// Reads a file, for example one that the resource refers to
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        for pack in self.packs.iter() {
+            if let Some(data) = pack.read(path) {
+                return data;
+            }
+        }
+        std::fs::read(path)
+    }
+}
+
+/// Marker trait of asset types, defined outside of the core
+///
+/// Assets of such types can be produced by an [`AssetImporter`] and stored in
+/// [`super::Assets`] as any other asset.
+///
+/// ```no_run
+/// use dotrix_core::assets::CustomAsset;
+/// use dotrix_core::Assets;
+///
+/// struct Level {
+///     tiles: Vec<u8>,
+/// }
+///
+/// impl CustomAsset for Level {}
+///
+/// let mut assets = Assets::new();
+/// assets.add_type::<Level>();
+/// let id = assets.store(Level { tiles: vec![0; 64] });
+/// ```
+pub trait CustomAsset: Any + Send + Sync {}
+
+/// Asset importer
+///
+/// Importers are registered in [`super::Assets`] for file extensions and are executed in
+/// loader threads. Imported assets are sent to the `sender` and stored, when assets are
+/// fetched. Other files, that the resource refers to, should be read using [`Files`].
+///
+/// ```no_run
+/// use dotrix_core::assets::{AssetImporter, CustomAsset, Files, ImportError, Response, Sender};
+/// use dotrix_core::Assets;
+///
+/// struct Level {
+///     tiles: Vec<u8>,
+/// }
+///
+/// impl CustomAsset for Level {}
+///
+/// struct LevelImporter;
+///
+/// impl AssetImporter for LevelImporter {
+///     fn extensions(&self) -> &[&str] {
+///         &["level"]
+///     }
+///
+///     fn import(
+///         &self,
+///         sender: &Sender,
+///         name: String,
+///         data: Vec<u8>,
+///         _files: &Files,
+///     ) -> Result<(), ImportError> {
+///         let level = Level { tiles: data };
+///         sender.lock().unwrap().send(Response::custom(name, level)).unwrap();
+///         Ok(())
+///     }
+/// }
+///
+/// let mut assets = Assets::new();
+/// assets.add_type::<Level>();
+/// assets.add_importer(LevelImporter);
+/// assets.import("assets/first.level");
+/// ```
+pub trait AssetImporter: Send + Sync + 'static {
+    /// Returns file extensions supported by the importer
+    fn extensions(&self) -> &[&str];
+
+    /// Imports assets from the file data
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        files: &Files,
+    ) -> Result<(), ImportError>;
+}
+
+impl Response {
+    /// Constructs a response with a [`CustomAsset`]
+    pub fn custom<T: CustomAsset>(name: String, asset: T) -> Self {
+        Response::Custom(Asset {
+            name,
+            asset: Box::new(asset),
+        })
+    }
+}
+
+/// Importer of PNG, JPEG and BMP images as [`super::Texture`] assets
+pub struct ImageImporter;
+
+impl AssetImporter for ImageImporter {
+    fn extensions(&self) -> &[&str] {
+        &["png", "jpg", "jpeg", "bmp"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        files: &Files,
+    ) -> Result<(), ImportError> {
+        let format = files
+            .path()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(image::ImageFormat::from_extension)
+            .ok_or(ImportError::NotImplemented("image format", None))?;
+        load_image(sender, name, data, format, files.settings().mipmaps)
+    }
+}
+
+/// Importer of WGSL shaders as [`super::Shader`] assets
+///
+/// Shaders are processed by the [`Preprocessor`] of [`super::Assets`], so they can include
+/// modules registered in it.
+pub struct WgslImporter {
+    preprocessor: Arc<RwLock<Preprocessor>>,
+}
+
+impl WgslImporter {
+    /// Constructs the importer, processing shaders by the shared preprocessor
+    pub fn new(preprocessor: Arc<RwLock<Preprocessor>>) -> Self {
+        Self { preprocessor }
+    }
+}
+
+impl AssetImporter for WgslImporter {
+    fn extensions(&self) -> &[&str] {
+        &["wgsl"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        _files: &Files,
+    ) -> Result<(), ImportError> {
+        load_wgsl(sender, name, data, &self.preprocessor.read().unwrap())
+    }
+}
+
+/// Importer of glTF models, both JSON and binary ones
+pub struct GltfImporter;
+
+impl AssetImporter for GltfImporter {
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        files: &Files,
+    ) -> Result<(), ImportError> {
+        load_gltf(sender, name, data, files)
+    }
+}
+
+/// Importer of KTX2 textures with GPU compressed formats and pre-built mip levels
+pub struct Ktx2Importer;
+
+impl AssetImporter for Ktx2Importer {
+    fn extensions(&self) -> &[&str] {
+        &["ktx2"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        files: &Files,
+    ) -> Result<(), ImportError> {
+        load_ktx2(sender, name, data, files.settings().mipmaps)
+    }
+}
+
+/// Importer of DDS textures with BC compressed formats and pre-built mip levels
+pub struct DdsImporter;
+
+impl AssetImporter for DdsImporter {
+    fn extensions(&self) -> &[&str] {
+        &["dds"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        _files: &Files,
+    ) -> Result<(), ImportError> {
+        load_dds(sender, name, data)
+    }
+}
+
+/// Importer of Radiance HDR images as float textures
+pub struct HdrImporter;
+
+impl AssetImporter for HdrImporter {
+    fn extensions(&self) -> &[&str] {
+        &["hdr"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        _files: &Files,
+    ) -> Result<(), ImportError> {
+        load_hdr(sender, name, data)
+    }
+}
+
+/// Importer of OpenEXR images as float textures
+pub struct ExrImporter;
+
+impl AssetImporter for ExrImporter {
+    fn extensions(&self) -> &[&str] {
+        &["exr"]
+    }
+
+    fn import(
+        &self,
+        sender: &Sender,
+        name: String,
+        data: Vec<u8>,
+        _files: &Files,
+    ) -> Result<(), ImportError> {
+        load_exr(sender, name, data)
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     path::Path,
     sync::{mpsc, Arc, Mutex},
 };
@@ -7,40 +8,71 @@ use gltf::{animation::util::ReadOutputs, buffer::Source, Gltf};
 
 use log::info;
 
-use crate::transform::Transform;
+use crate::{color::Color, transform::Transform};
 use dotrix_math::{Mat4, Quat, Vec3};
 
 use super::{
     animation::{Animation, Interpolation},
+    importer::Files,
     loader::{load_image, Asset, ImportError, Response},
+    material::Material,
     mesh::Mesh,
+    prefab::{Node, NodeIndex, Prefab, Primitive},
     skin::{Joint, JointId, JointIndex, Skin},
+    texture::Texture,
 };
 
 pub fn load_gltf(
     sender: &Arc<Mutex<mpsc::Sender<Response>>>,
     name: String,
     data: Vec<u8>,
-    path: &Path,
+    files: &Files,
 ) -> Result<(), ImportError> {
+    // binary glTF is recognized by its magic, the binary chunk becomes the blob
     let gltf = Gltf::from_slice(&data)?;
-    let buffers = load_buffers(&gltf, path)?;
+    let buffers = load_buffers(&gltf, files)?;
+    let images = load_images(sender, &name, &gltf, &buffers, files)?;
+    let sources = Sources {
+        buffers,
+        images,
+        mipmaps: files.settings().mipmaps,
+    };
+    let default_scene = gltf
+        .default_scene()
+        .or_else(|| gltf.scenes().next())
+        .map(|scene| scene.index());
 
     for scene in gltf.scenes() {
+        let mut prefab = Prefab::default();
         for node in scene.nodes() {
-            load_node(sender, &name, &node, None, &buffers)?;
+            load_node(sender, &name, &node, None, &mut prefab, None, &sources)?;
         }
+
+        let prefab_name = if Some(scene.index()) == default_scene {
+            [name.as_str(), "prefab"].join("::")
+        } else {
+            format!("{}.scene[{}]::prefab", name, scene.index())
+        };
+
+        info!("importing prefab as `{}`", prefab_name);
+        sender
+            .lock()
+            .unwrap()
+            .send(Response::Prefab(Asset {
+                name: prefab_name,
+                asset: Box::new(prefab),
+            }))
+            .unwrap();
     }
 
     for animation in gltf.animations() {
-        load_animation(sender, &name, &animation, &buffers);
+        load_animation(sender, &name, &animation, &sources.buffers);
     }
 
     Ok(())
 }
 
-fn load_buffers(gltf: &Gltf, path: &Path) -> Result<Vec<Vec<u8>>, ImportError> {
-    const URI_BASE64: &str = "data:application/octet-stream;base64,";
+fn load_buffers(gltf: &Gltf, files: &Files) -> Result<Vec<Vec<u8>>, ImportError> {
     let mut buffers = Vec::new();
 
     for buffer in gltf.buffers() {
@@ -53,11 +85,7 @@ fn load_buffers(gltf: &Gltf, path: &Path) -> Result<Vec<Vec<u8>>, ImportError> {
                 }
             }
             Source::Uri(uri) => {
-                buffers.push(if let Some(stripped) = uri.strip_prefix(URI_BASE64) {
-                    base64::decode(stripped)?
-                } else {
-                    std::fs::read(path.parent().unwrap().join(uri))?
-                });
+                buffers.push(read_uri(uri, files)?.0);
             }
         }
     }
@@ -65,6 +93,116 @@ fn load_buffers(gltf: &Gltf, path: &Path) -> Result<Vec<Vec<u8>>, ImportError> {
     Ok(buffers)
 }
 
+/// Binary data of a glTF file
+struct Sources {
+    buffers: Vec<Vec<u8>>,
+    images: Vec<Image>,
+    /// Generate mip levels of textures
+    mipmaps: bool,
+}
+
+/// Image of a glTF file, imported as a texture
+struct Image {
+    /// Name of the texture
+    name: String,
+    /// Data of images, that materials split into channels
+    data: Option<Vec<u8>>,
+    format: image::ImageFormat,
+}
+
+/// Reads all images of the glTF file and imports each of them as a texture
+///
+/// Images are named by their names in the file or by indices, if names are missing. Materials
+/// reference the textures by these names.
+fn load_images(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: &str,
+    gltf: &Gltf,
+    buffers: &[Vec<u8>],
+    files: &Files,
+) -> Result<Vec<Image>, ImportError> {
+    let mut images = Vec::new();
+    let channels = gltf
+        .materials()
+        .flat_map(|material| {
+            [
+                material
+                    .pbr_metallic_roughness()
+                    .metallic_roughness_texture()
+                    .map(|info| info.texture().source().index()),
+                material
+                    .occlusion_texture()
+                    .map(|info| info.texture().source().index()),
+            ]
+        })
+        .flatten()
+        .collect::<HashSet<_>>();
+
+    for gltf_image in gltf.images() {
+        let (data, mime_type) = match gltf_image.source() {
+            gltf::image::Source::Uri { uri, mime_type } => {
+                let (data, uri_mime_type) = read_uri(uri, files)?;
+                (data, mime_type.map(String::from).or(uri_mime_type))
+            }
+            gltf::image::Source::View { view, mime_type } => {
+                let offset = view.offset();
+                let tail = offset + view.length();
+                let data = buffers[view.buffer().index()].get(offset..tail).ok_or(
+                    ImportError::Corruption("image buffer view is out of bounds"),
+                )?;
+                (data.to_vec(), Some(String::from(mime_type)))
+            }
+        };
+
+        let format = match mime_type.as_deref() {
+            Some("image/png") => image::ImageFormat::Png,
+            Some("image/jpeg") => image::ImageFormat::Jpeg,
+            // mime type is optional for external images
+            _ => image::guess_format(&data)
+                .map_err(|_| ImportError::NotImplemented("mime type", mime_type.clone()))?,
+        };
+
+        // images are named by index, so names never clash with the ones of nodes
+        let image_name = format!("{}.image[{}]", name, gltf_image.index());
+        info!("importing texture as `{}`", image_name);
+        let kept = channels.contains(&gltf_image.index()).then(|| data.clone());
+        load_image(
+            sender,
+            image_name.clone(),
+            data,
+            format,
+            files.settings().mipmaps,
+        )?;
+
+        images.push(Image {
+            name: image_name,
+            data: kept,
+            format,
+        });
+    }
+
+    Ok(images)
+}
+
+/// Reads data of an URI, that is a data URI or a path relative to the glTF file
+///
+/// Returns the data and the mime type of a data URI
+fn read_uri(uri: &str, files: &Files) -> Result<(Vec<u8>, Option<String>), ImportError> {
+    if let Some(data_uri) = uri.strip_prefix("data:") {
+        let (header, data) = data_uri
+            .split_once(',')
+            .ok_or(ImportError::Corruption("data URI has no data"))?;
+        let mime_type = header
+            .strip_suffix(";base64")
+            .ok_or_else(|| ImportError::NotImplemented("data URI encoding", Some(header.into())))?;
+        let mime_type = Some(mime_type).filter(|m| !m.is_empty()).map(String::from);
+        Ok((base64::decode(data)?, mime_type))
+    } else {
+        let directory = files.path().parent().unwrap_or_else(|| Path::new(""));
+        Ok((files.read(&directory.join(uri))?, None))
+    }
+}
+
 fn load_joints(joints: &mut Vec<Joint>, node: &gltf::Node, parent_id: Option<JointId>) {
     let local_transform = Transform::from(node.transform());
     let id = node.index();
@@ -85,8 +223,12 @@ fn load_node(
     name: &str,
     node: &gltf::Node,
     root: Option<&gltf::Node>,
-    buffers: &[Vec<u8>],
+    prefab: &mut Prefab,
+    parent: Option<NodeIndex>,
+    sources: &Sources,
 ) -> Result<(), ImportError> {
+    let buffers = &sources.buffers;
+    let mut skin_name = None;
     if let Some(skin) = node.skin() {
         let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
         let inverse_bind_matrices = reader
@@ -109,6 +251,7 @@ fn load_node(
         );
 
         info!("importing skin as `{}`", asset_name);
+        skin_name = Some(asset_name.clone());
         sender
             .lock()
             .unwrap()
@@ -119,16 +262,32 @@ fn load_node(
             .unwrap();
     }
 
+    let mut primitives = Vec::new();
     if let Some(mesh) = node.mesh() {
-        for primitive in mesh.primitives() {
-            load_mesh(sender, name, &primitive, buffers)?;
-            let material = primitive.material();
-            if let Some(texture) = material.pbr_metallic_roughness().base_color_texture() {
-                load_texture(sender, name, &texture, buffers)?;
-            }
+        for (i, primitive) in mesh.primitives().enumerate() {
+            // primitives after the first one get their own names, so they don't override it
+            let primitive_name = if i == 0 {
+                String::from(name)
+            } else {
+                format!("{}.primitive[{}]", name, i)
+            };
+            let mesh = load_mesh(sender, &primitive_name, &primitive, buffers)?;
+            let material = load_material(sender, &primitive_name, &primitive.material(), sources)?;
+            primitives.push(Primitive {
+                mesh,
+                material: Some(material),
+            });
         }
     }
 
+    let index = prefab.add(Node {
+        name: node.name().map(String::from),
+        parent,
+        transform: Transform::from(node.transform()),
+        skin: skin_name,
+        primitives,
+    });
+
     let root = root.or(Some(node));
     for child in node.children() {
         let child_name = if let Some(child_name) = child.name() {
@@ -137,7 +296,15 @@ fn load_node(
             format!("{}.node[{}]", name, child.index())
         };
 
-        load_node(sender, &child_name, &child, root, buffers)?;
+        load_node(
+            sender,
+            &child_name,
+            &child,
+            root,
+            prefab,
+            Some(index),
+            sources,
+        )?;
     }
 
     Ok(())
@@ -148,7 +315,7 @@ fn load_mesh(
     name: &str,
     primitive: &gltf::Primitive,
     buffers: &[Vec<u8>],
-) -> Result<(), ImportError> {
+) -> Result<String, ImportError> {
     let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
     let mode = primitive.mode();
 
@@ -212,62 +379,151 @@ fn load_mesh(
         .lock()
         .unwrap()
         .send(Response::Mesh(Asset {
-            name,
+            name: name.clone(),
             asset: Box::new(mesh),
         }))
         .unwrap();
 
-    Ok(())
+    Ok(name)
 }
 
-fn load_texture(
+fn load_material(
     sender: &Arc<Mutex<mpsc::Sender<Response>>>,
     name: &str,
-    texture: &gltf::texture::Info,
-    buffers: &[Vec<u8>],
-) -> Result<(), ImportError> {
-    let source = texture.texture().source().source();
-    let name = [name, "texture"].join("::");
-    info!("importing texture as `{}`", name);
+    gltf_material: &gltf::Material,
+    sources: &Sources,
+) -> Result<String, ImportError> {
+    let images = &sources.images;
+    let mipmaps = sources.mipmaps;
+    let pbr = gltf_material.pbr_metallic_roughness();
+    let [r, g, b, a] = pbr.base_color_factor();
+    let [er, eg, eb] = gltf_material.emissive_factor();
+    let mut material = Material {
+        albedo: Color::rgba(r, g, b, a),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        emissive: Color::rgb(er, eg, eb),
+        ..Default::default()
+    };
 
-    let (data, format) = match source {
-        gltf::image::Source::Uri { uri, .. } => {
-            const URI_IMAGE_PNG: &str = "data:image/png;base64,";
-
-            if !uri.starts_with(URI_IMAGE_PNG) {
-                return Ok(());
-                /* TODO: this should be permissive
-                return Err(ImportError::NotImplemented(
-                    "mime type",
-                    mime_type.map(String::from),
-                ));
-                */
-            }
+    if let Some(info) = pbr.base_color_texture() {
+        material.texture = Some(image_name(&info.texture(), images));
+    }
 
-            let data = base64::decode(&uri[URI_IMAGE_PNG.len()..])?;
-            (data, image::ImageFormat::Png)
-        }
+    if let Some(info) = gltf_material.normal_texture() {
+        material.normal_texture = Some(image_name(&info.texture(), images));
+    }
 
-        gltf::image::Source::View { view, mime_type } => {
-            if mime_type != "image/png" {
-                return Err(ImportError::NotImplemented(
-                    "mime type",
-                    Some(String::from(mime_type)),
-                ));
-            }
+    if let Some(info) = gltf_material.emissive_texture() {
+        material.emissive_texture = Some(image_name(&info.texture(), images));
+    }
 
-            let index = view.buffer().index();
-            let offset = view.offset();
-            let tail = offset + view.length();
-            let data = &buffers[index][offset..tail];
+    // glTF packs roughness into the green channel and metallic into the blue one
+    if let Some(info) = pbr.metallic_roughness_texture() {
+        let image = decode_texture(&info.texture(), images)?;
+        let metallic = pbr.metallic_factor();
+        let roughness = pbr.roughness_factor();
+        material.metallic_texture = Some(send_channel(
+            sender,
+            name,
+            "metallic",
+            &image,
+            mipmaps,
+            |p| p[2] as f32 / 255.0 * metallic,
+        ));
+        material.roughness_texture = Some(send_channel(
+            sender,
+            name,
+            "roughness",
+            &image,
+            mipmaps,
+            |p| p[1] as f32 / 255.0 * roughness,
+        ));
+    }
 
-            (data.to_vec(), image::ImageFormat::Png)
-        }
+    // occlusion is stored in the red channel
+    if let Some(info) = gltf_material.occlusion_texture() {
+        let image = decode_texture(&info.texture(), images)?;
+        let strength = info.strength();
+        material.ao_texture = Some(send_channel(sender, name, "ao", &image, mipmaps, |p| {
+            1.0 + strength * (p[0] as f32 / 255.0 - 1.0)
+        }));
+    }
+
+    let name = [name, "material"].join("::");
+    info!("importing material as `{}`", name);
+    sender
+        .lock()
+        .unwrap()
+        .send(Response::Material(Asset {
+            name: name.clone(),
+            asset: Box::new(material),
+        }))
+        .unwrap();
+
+    Ok(name)
+}
+
+/// Returns name of the texture imported from the image of the glTF texture
+fn image_name(texture: &gltf::Texture, images: &[Image]) -> String {
+    images[texture.source().index()].name.clone()
+}
+
+fn decode_texture(
+    texture: &gltf::Texture,
+    images: &[Image],
+) -> Result<image::RgbaImage, ImportError> {
+    let image = &images[texture.source().index()];
+    let data = image
+        .data
+        .as_deref()
+        .expect("Data of images split into channels must be kept");
+    let image = image::load_from_memory_with_format(data, image.format)?;
+    Ok(image.into_rgba8())
+}
+
+/// Sends a single channel texture, where each pixel is mapped to a value in range from 0 to 1
+fn send_channel(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: &str,
+    suffix: &str,
+    image: &image::RgbaImage,
+    mipmaps: bool,
+    map: impl Fn(&[u8]) -> f32,
+) -> String {
+    let name = [name, suffix].join("::");
+    info!("importing texture as `{}`", name);
+
+    let (width, height) = image.dimensions();
+    let data = image
+        .pixels()
+        .flat_map(|pixel| {
+            let value = (map(&pixel.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+            [value; 4]
+        })
+        .collect::<Vec<_>>();
+
+    let mut texture = Texture {
+        width,
+        height,
+        depth: 1,
+        data,
+        ..Default::default()
     };
+    if mipmaps {
+        texture.generate_mipmaps();
+    }
 
-    load_image(sender, name, data, format)?;
+    sender
+        .lock()
+        .unwrap()
+        .send(Response::Texture(Asset {
+            name: name.clone(),
+            asset: Box::new(texture),
+        }))
+        .unwrap();
 
-    Ok(())
+    name
 }
 
 fn load_animation(
@@ -342,3 +598,302 @@ fn mode_to_string(mode: gltf::mesh::Mode) -> String {
     };
     String::from(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+    use std::path::Path;
+
+    const POSITIONS: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+    /// Encodes 1x1 PNG image with the pixel
+    fn png(pixel: [u8; 4]) -> Vec<u8> {
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba(pixel)))
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn textures(receiver: &mpsc::Receiver<Response>) -> std::collections::HashMap<String, Vec<u8>> {
+        let mut textures = std::collections::HashMap::new();
+        while let Ok(response) = receiver.try_recv() {
+            if let Response::Texture(texture) = response {
+                textures.insert(texture.name, texture.asset.data);
+            }
+        }
+        textures
+    }
+
+    fn triangle_gltf(material: &str) -> Vec<u8> {
+        let buffer = base64::encode(bytemuck::cast_slice(&POSITIONS));
+        let png = png([51, 204, 102, 255]);
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [
+                    {{ "name": "Body", "children": [1] }},
+                    {{ "name": "Wheel", "mesh": 0, "translation": [1.0, 2.0, 3.0] }}
+                ],
+                "meshes": [{{
+                    "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "material": 0 }}]
+                }}],
+                "materials": [{}],
+                "textures": [{{ "source": 0 }}],
+                "images": [{{ "uri": "data:image/png;base64,{}" }}],
+                "accessors": [{{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }}],
+                "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+                "buffers": [{{
+                    "byteLength": 36,
+                    "uri": "data:application/octet-stream;base64,{}"
+                }}]
+            }}"#,
+            material,
+            base64::encode(&png),
+            buffer
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn prefab_import() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Arc::new(Mutex::new(sender));
+
+        load_gltf(
+            &sender,
+            String::from("car"),
+            triangle_gltf(
+                r#"{
+                    "pbrMetallicRoughness": {
+                        "baseColorFactor": [1.0, 0.5, 0.0, 1.0],
+                        "metallicFactor": 0.25,
+                        "roughnessFactor": 0.75
+                    }
+                }"#,
+            ),
+            &Files::new(Path::new("car.gltf"), &[]),
+        )
+        .unwrap();
+
+        let mut meshes = Vec::new();
+        let mut prefabs = Vec::new();
+        let mut materials = Vec::new();
+        while let Ok(response) = receiver.try_recv() {
+            match response {
+                Response::Mesh(mesh) => meshes.push(mesh.name),
+                Response::Prefab(prefab) => prefabs.push(prefab),
+                Response::Material(material) => materials.push(material),
+                _ => (),
+            }
+        }
+
+        assert_eq!(meshes, vec![String::from("car::Wheel::mesh")]);
+        assert_eq!(prefabs.len(), 1);
+        assert_eq!(prefabs[0].name, "car::prefab");
+
+        let prefab = &prefabs[0].asset;
+        assert_eq!(prefab.nodes.len(), 2);
+        assert_eq!(prefab.roots().count(), 1);
+
+        let (index, body) = prefab.roots().next().unwrap();
+        assert_eq!(body.name.as_deref(), Some("Body"));
+        assert!(body.primitives.is_empty());
+
+        let children = prefab.children(index).collect::<Vec<_>>();
+        assert_eq!(children.len(), 1);
+        let wheel = children[0].1;
+        assert_eq!(wheel.name.as_deref(), Some("Wheel"));
+        assert_eq!(wheel.transform.translate, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(wheel.primitives.len(), 1);
+
+        let primitive = &wheel.primitives[0];
+        assert_eq!(primitive.mesh, "car::Wheel::mesh");
+        assert_eq!(primitive.material.as_deref(), Some("car::Wheel::material"));
+
+        let material = &materials[0];
+        assert_eq!(material.name, "car::Wheel::material");
+        assert_eq!(material.asset.texture, None);
+        assert_eq!(material.asset.albedo.g, 0.5);
+        assert_eq!(material.asset.metallic, 0.25);
+        assert_eq!(material.asset.roughness, 0.75);
+    }
+
+    #[test]
+    fn material_import() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Arc::new(Mutex::new(sender));
+
+        load_gltf(
+            &sender,
+            String::from("car"),
+            triangle_gltf(
+                r#"{
+                    "pbrMetallicRoughness": {
+                        "baseColorTexture": { "index": 0 },
+                        "metallicFactor": 0.5,
+                        "metallicRoughnessTexture": { "index": 0 }
+                    },
+                    "normalTexture": { "index": 0 },
+                    "occlusionTexture": { "index": 0, "strength": 0.5 }
+                }"#,
+            ),
+            &Files::new(Path::new("car.gltf"), &[]),
+        )
+        .unwrap();
+
+        let mut textures = std::collections::HashMap::new();
+        let mut materials = Vec::new();
+        while let Ok(response) = receiver.try_recv() {
+            match response {
+                Response::Texture(texture) => {
+                    textures.insert(texture.name, texture.asset.data);
+                }
+                Response::Material(material) => materials.push(material.asset),
+                _ => (),
+            }
+        }
+
+        assert_eq!(materials.len(), 1);
+        let material = &materials[0];
+        // materials reference textures of images, instead of importing own copies
+        assert_eq!(material.texture.as_deref(), Some("car.image[0]"));
+        assert_eq!(material.normal_texture.as_deref(), Some("car.image[0]"));
+        assert_eq!(material.emissive_texture, None);
+        assert_eq!(textures.len(), 4);
+
+        // source pixel is [51, 204, 102, 255]
+        assert_eq!(textures["car.image[0]"], vec![51, 204, 102, 255]);
+        let metallic = material.metallic_texture.as_deref().unwrap();
+        assert_eq!(textures[metallic], vec![51; 4]);
+        let roughness = material.roughness_texture.as_deref().unwrap();
+        assert_eq!(textures[roughness], vec![204; 4]);
+        let ao = material.ao_texture.as_deref().unwrap();
+        assert_eq!(textures[ao], vec![153; 4]);
+    }
+
+    #[test]
+    fn glb_import() {
+        let png = png([10, 20, 30, 255]);
+        let mut bin = Vec::from(bytemuck::cast_slice::<f32, u8>(&POSITIONS));
+        bin.extend_from_slice(&png);
+        while bin.len() % 4 != 0 {
+            bin.push(0);
+        }
+        let mut json = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [{{ "name": "Box", "mesh": 0 }}],
+                "meshes": [{{
+                    "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "material": 0 }}]
+                }}],
+                "materials": [{{
+                    "pbrMetallicRoughness": {{ "baseColorTexture": {{ "index": 0 }} }}
+                }}],
+                "textures": [{{ "source": 0 }}],
+                "images": [{{ "name": "Paint", "bufferView": 1, "mimeType": "image/png" }}],
+                "accessors": [{{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": {} }}
+                ],
+                "buffers": [{{ "byteLength": {} }}]
+            }}"#,
+            png.len(),
+            bin.len()
+        )
+        .into_bytes();
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+
+        let mut glb = Vec::new();
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&bin);
+
+        let (sender, receiver) = mpsc::channel();
+        let sender = Arc::new(Mutex::new(sender));
+        load_gltf(
+            &sender,
+            String::from("crate"),
+            glb,
+            &Files::new(Path::new("crate.glb"), &[]),
+        )
+        .unwrap();
+
+        let textures = textures(&receiver);
+        assert_eq!(textures.len(), 1);
+        assert_eq!(textures["crate.image[0]"], vec![10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn image_sources() {
+        let dir = TempDir::new("gltf");
+        std::fs::create_dir_all(dir.join("textures")).unwrap();
+        std::fs::write(dir.join("textures/external.png"), png([1, 2, 3, 255])).unwrap();
+
+        let embedded = png([4, 5, 6, 255]);
+        let viewed = png([7, 8, 9, 255]);
+        let gltf = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "images": [
+                    {{ "name": "External", "uri": "textures/external.png" }},
+                    {{ "uri": "data:image/png;base64,{}" }},
+                    {{ "name": "Viewed", "bufferView": 0, "mimeType": "image/png" }}
+                ],
+                "bufferViews": [{{ "buffer": 0, "byteLength": {} }}],
+                "buffers": [{{
+                    "byteLength": {},
+                    "uri": "data:application/gltf-buffer;base64,{}"
+                }}]
+            }}"#,
+            base64::encode(&embedded),
+            viewed.len(),
+            viewed.len(),
+            base64::encode(&viewed)
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        let sender = Arc::new(Mutex::new(sender));
+        let path = dir.join("scene.gltf");
+        load_gltf(
+            &sender,
+            String::from("scene"),
+            gltf.into_bytes(),
+            &Files::new(&path, &[]),
+        )
+        .unwrap();
+
+        let textures = textures(&receiver);
+        assert_eq!(textures.len(), 3);
+        assert_eq!(textures["scene.image[0]"], vec![1, 2, 3, 255]);
+        assert_eq!(textures["scene.image[1]"], vec![4, 5, 6, 255]);
+        assert_eq!(textures["scene.image[2]"], vec![7, 8, 9, 255]);
+    }
+}
@@ -0,0 +1,467 @@
+//! Loaders of GPU compressed and HDR textures
+use std::io::Cursor;
+use std::sync::{mpsc, Arc, Mutex};
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use half::f16;
+use wgpu::TextureFormat;
+
+use super::{
+    loader::{Asset, ImportError, Response},
+    texture::Texture,
+};
+
+/// Loads a KTX2 texture with its mip levels
+///
+/// Supercompressed textures, arrays, cubemaps and 3D textures are not supported. Levels are
+/// generated, if the file asks for it and `mipmaps` are enabled.
+pub fn load_ktx2(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: String,
+    data: Vec<u8>,
+    mipmaps: bool,
+) -> Result<(), ImportError> {
+    let reader = ktx2::Reader::new(data.as_slice())?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        return Err(ImportError::NotImplemented(
+            "KTX2 supercompression",
+            Some(format!("{:?}", scheme)),
+        ));
+    }
+    if header.layer_count > 1 || header.face_count > 1 || header.pixel_depth > 1 {
+        return Err(ImportError::NotImplemented("KTX2 texture type", None));
+    }
+
+    let format = header.format.and_then(ktx2_format).ok_or_else(|| {
+        ImportError::NotImplemented("KTX2 format", header.format.map(|f| format!("{:?}", f)))
+    })?;
+
+    let mut levels = reader.levels().map(Vec::from);
+    let data = levels
+        .next()
+        .ok_or(ImportError::Corruption("KTX2 texture has no levels"))?;
+
+    let mut texture = Texture {
+        width: header.pixel_width,
+        height: header.pixel_height.max(1),
+        depth: 1,
+        data,
+        format,
+        levels: levels.collect(),
+        ..Default::default()
+    };
+    // zero levels mean that they should be generated
+    if header.level_count == 0 && mipmaps {
+        texture.generate_mipmaps();
+    }
+    send_texture(sender, name, texture);
+    Ok(())
+}
+
+/// Loads a DDS texture with its mip levels
+///
+/// Arrays, cubemaps and volume textures are not supported.
+pub fn load_dds(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: String,
+    data: Vec<u8>,
+) -> Result<(), ImportError> {
+    let dds = Dds::read(data.as_slice())?;
+
+    if dds.get_num_array_layers() > 1 || dds.get_depth() > 1 {
+        return Err(ImportError::NotImplemented("DDS texture type", None));
+    }
+
+    let format = dds
+        .get_dxgi_format()
+        .and_then(dxgi_format)
+        .or_else(|| dds.get_d3d_format().and_then(d3d_format))
+        .ok_or_else(|| {
+            let format = dds
+                .get_dxgi_format()
+                .map(|f| format!("{:?}", f))
+                .or_else(|| dds.get_d3d_format().map(|f| format!("{:?}", f)));
+            ImportError::NotImplemented("DDS format", format)
+        })?;
+
+    let width = dds.get_width();
+    let height = dds.get_height();
+    // the only array layer, `Dds::get_data` can't compute its size without mip levels count
+    let mut layer = dds.data.as_slice();
+    let mut levels = Vec::new();
+    // files without mip levels may have zero count of them
+    for level in 0..dds.get_num_mipmap_levels().max(1) {
+        let size = level_size(format, (width >> level).max(1), (height >> level).max(1));
+        if layer.len() < size {
+            return Err(ImportError::Corruption("DDS mip level is incomplete"));
+        }
+        let (data, rest) = layer.split_at(size);
+        levels.push(data.to_vec());
+        layer = rest;
+    }
+    let data = levels.remove(0);
+
+    send_texture(
+        sender,
+        name,
+        Texture {
+            width,
+            height,
+            depth: 1,
+            data,
+            format,
+            levels,
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+/// Loads a Radiance HDR image as an `Rgba16Float` texture
+pub fn load_hdr(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: String,
+    data: Vec<u8>,
+) -> Result<(), ImportError> {
+    let decoder = image::codecs::hdr::HdrDecoder::new(Cursor::new(data))?;
+    let metadata = decoder.metadata();
+    let pixels = decoder.read_image_hdr()?;
+    let data = pixels
+        .iter()
+        .flat_map(|pixel| {
+            let [r, g, b] = pixel.0;
+            [r, g, b, 1.0]
+        })
+        .flat_map(|value| f16::from_f32(value).to_le_bytes())
+        .collect();
+
+    send_float_texture(sender, name, metadata.width, metadata.height, data);
+    Ok(())
+}
+
+/// Loads the first RGB(A) layer of an OpenEXR image as an `Rgba16Float` texture
+pub fn load_exr(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: String,
+    data: Vec<u8>,
+) -> Result<(), ImportError> {
+    use exr::prelude::{read, ReadChannels, ReadLayers};
+
+    let image = read()
+        .no_deep_data()
+        .largest_resolution_level()
+        .rgba_channels(
+            |resolution, _| {
+                let width = resolution.width();
+                (width, vec![f16::ZERO; width * resolution.height() * 4])
+            },
+            |(width, pixels), position, (r, g, b, a): (f16, f16, f16, f16)| {
+                let offset = (position.y() * *width + position.x()) * 4;
+                pixels[offset..offset + 4].copy_from_slice(&[r, g, b, a]);
+            },
+        )
+        .first_valid_layer()
+        .all_attributes()
+        .from_buffered(Cursor::new(data))?;
+
+    let size = image.layer_data.size;
+    let (_, pixels) = image.layer_data.channel_data.pixels;
+    let data = pixels
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+
+    send_float_texture(
+        sender,
+        name,
+        size.width() as u32,
+        size.height() as u32,
+        data,
+    );
+    Ok(())
+}
+
+fn send_float_texture(
+    sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    name: String,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+) {
+    send_texture(
+        sender,
+        name,
+        Texture {
+            width,
+            height,
+            depth: 1,
+            data,
+            format: TextureFormat::Rgba16Float,
+            ..Default::default()
+        },
+    );
+}
+
+fn send_texture(sender: &Arc<Mutex<mpsc::Sender<Response>>>, name: String, texture: Texture) {
+    sender
+        .lock()
+        .unwrap()
+        .send(Response::Texture(Asset {
+            name,
+            asset: Box::new(texture),
+        }))
+        .unwrap();
+}
+
+/// Returns size of a mip level in bytes
+fn level_size(format: TextureFormat, width: u32, height: u32) -> usize {
+    let info = format.describe();
+    let (block_width, block_height) = info.block_dimensions;
+    let columns = width.div_ceil(block_width as u32);
+    let rows = height.div_ceil(block_height as u32);
+    (columns * rows * info.block_size as u32) as usize
+}
+
+fn ktx2_format(format: ktx2::Format) -> Option<TextureFormat> {
+    use ktx2::Format;
+
+    Some(match format {
+        Format::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        Format::R16G16B16A16_SFLOAT => TextureFormat::Rgba16Float,
+        Format::R32G32B32A32_SFLOAT => TextureFormat::Rgba32Float,
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        Format::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        Format::BC4_SNORM_BLOCK => TextureFormat::Bc4RSnorm,
+        Format::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        Format::BC5_SNORM_BLOCK => TextureFormat::Bc5RgSnorm,
+        Format::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        Format::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hRgbSfloat,
+        Format::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        Format::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        Format::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1Unorm,
+        Format::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        Format::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        Format::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11Unorm,
+        Format::EAC_R11_SNORM_BLOCK => TextureFormat::EacR11Snorm,
+        Format::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11Unorm,
+        Format::EAC_R11G11_SNORM_BLOCK => TextureFormat::EacRg11Snorm,
+        Format::ASTC_4x4_UNORM_BLOCK => TextureFormat::Astc4x4RgbaUnorm,
+        Format::ASTC_4x4_SRGB_BLOCK => TextureFormat::Astc4x4RgbaUnormSrgb,
+        Format::ASTC_5x4_UNORM_BLOCK => TextureFormat::Astc5x4RgbaUnorm,
+        Format::ASTC_5x4_SRGB_BLOCK => TextureFormat::Astc5x4RgbaUnormSrgb,
+        Format::ASTC_5x5_UNORM_BLOCK => TextureFormat::Astc5x5RgbaUnorm,
+        Format::ASTC_5x5_SRGB_BLOCK => TextureFormat::Astc5x5RgbaUnormSrgb,
+        Format::ASTC_6x5_UNORM_BLOCK => TextureFormat::Astc6x5RgbaUnorm,
+        Format::ASTC_6x5_SRGB_BLOCK => TextureFormat::Astc6x5RgbaUnormSrgb,
+        Format::ASTC_6x6_UNORM_BLOCK => TextureFormat::Astc6x6RgbaUnorm,
+        Format::ASTC_6x6_SRGB_BLOCK => TextureFormat::Astc6x6RgbaUnormSrgb,
+        Format::ASTC_8x5_UNORM_BLOCK => TextureFormat::Astc8x5RgbaUnorm,
+        Format::ASTC_8x5_SRGB_BLOCK => TextureFormat::Astc8x5RgbaUnormSrgb,
+        Format::ASTC_8x6_UNORM_BLOCK => TextureFormat::Astc8x6RgbaUnorm,
+        Format::ASTC_8x6_SRGB_BLOCK => TextureFormat::Astc8x6RgbaUnormSrgb,
+        Format::ASTC_8x8_UNORM_BLOCK => TextureFormat::Astc8x8RgbaUnorm,
+        Format::ASTC_8x8_SRGB_BLOCK => TextureFormat::Astc8x8RgbaUnormSrgb,
+        Format::ASTC_10x5_UNORM_BLOCK => TextureFormat::Astc10x5RgbaUnorm,
+        Format::ASTC_10x5_SRGB_BLOCK => TextureFormat::Astc10x5RgbaUnormSrgb,
+        Format::ASTC_10x6_UNORM_BLOCK => TextureFormat::Astc10x6RgbaUnorm,
+        Format::ASTC_10x6_SRGB_BLOCK => TextureFormat::Astc10x6RgbaUnormSrgb,
+        Format::ASTC_10x8_UNORM_BLOCK => TextureFormat::Astc10x8RgbaUnorm,
+        Format::ASTC_10x8_SRGB_BLOCK => TextureFormat::Astc10x8RgbaUnormSrgb,
+        Format::ASTC_10x10_UNORM_BLOCK => TextureFormat::Astc10x10RgbaUnorm,
+        Format::ASTC_10x10_SRGB_BLOCK => TextureFormat::Astc10x10RgbaUnormSrgb,
+        Format::ASTC_12x10_UNORM_BLOCK => TextureFormat::Astc12x10RgbaUnorm,
+        Format::ASTC_12x10_SRGB_BLOCK => TextureFormat::Astc12x10RgbaUnormSrgb,
+        Format::ASTC_12x12_UNORM_BLOCK => TextureFormat::Astc12x12RgbaUnorm,
+        Format::ASTC_12x12_SRGB_BLOCK => TextureFormat::Astc12x12RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn dxgi_format(format: DxgiFormat) -> Option<TextureFormat> {
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => TextureFormat::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::R16G16B16A16_Float => TextureFormat::Rgba16Float,
+        DxgiFormat::R32G32B32A32_Float => TextureFormat::Rgba32Float,
+        DxgiFormat::BC1_UNorm => TextureFormat::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC2_UNorm => TextureFormat::Bc2RgbaUnorm,
+        DxgiFormat::BC2_UNorm_sRGB => TextureFormat::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm => TextureFormat::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_UNorm => TextureFormat::Bc4RUnorm,
+        DxgiFormat::BC4_SNorm => TextureFormat::Bc4RSnorm,
+        DxgiFormat::BC5_UNorm => TextureFormat::Bc5RgUnorm,
+        DxgiFormat::BC5_SNorm => TextureFormat::Bc5RgSnorm,
+        DxgiFormat::BC6H_UF16 => TextureFormat::Bc6hRgbUfloat,
+        DxgiFormat::BC6H_SF16 => TextureFormat::Bc6hRgbSfloat,
+        DxgiFormat::BC7_UNorm => TextureFormat::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn d3d_format(format: D3DFormat) -> Option<TextureFormat> {
+    Some(match format {
+        D3DFormat::A8B8G8R8 => TextureFormat::Rgba8Unorm,
+        D3DFormat::A16B16G16R16F => TextureFormat::Rgba16Float,
+        D3DFormat::A32B32G32R32F => TextureFormat::Rgba32Float,
+        D3DFormat::DXT1 => TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT2 | D3DFormat::DXT3 => TextureFormat::Bc2RgbaUnorm,
+        D3DFormat::DXT4 | D3DFormat::DXT5 => TextureFormat::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receive(load: impl FnOnce(&Arc<Mutex<mpsc::Sender<Response>>>)) -> Texture {
+        let (sender, receiver) = mpsc::channel();
+        load(&Arc::new(Mutex::new(sender)));
+        match receiver.try_recv() {
+            Ok(Response::Texture(texture)) => *texture.asset,
+            _ => panic!("Texture was not loaded"),
+        }
+    }
+
+    #[test]
+    fn ktx2_levels() {
+        // 8x8 BC1 texture: 4 blocks on the base level and 1 block on the next one
+        let levels = [vec![1; 32], vec![2; 8]];
+        let data_offset = 80 + 24 * levels.len();
+
+        let mut ktx2 = Vec::from(*b"\xABKTX 20\xBB\r\n\x1A\n");
+        let header = [
+            ktx2::Format::BC1_RGBA_SRGB_BLOCK.0.get(),
+            1, // type size
+            8, // width
+            8, // height
+            0, // depth
+            0, // layers
+            1, // faces
+            levels.len() as u32,
+            0, // supercompression
+        ];
+        for value in header {
+            ktx2.extend_from_slice(&value.to_le_bytes());
+        }
+        // data format descriptor, key/value data and supercompression global data
+        ktx2.extend_from_slice(&[0; 32]);
+        let mut offset = data_offset;
+        for level in levels.iter() {
+            ktx2.extend_from_slice(&(offset as u64).to_le_bytes());
+            ktx2.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            ktx2.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            offset += level.len();
+        }
+        for level in levels.iter() {
+            ktx2.extend_from_slice(level);
+        }
+
+        let texture =
+            receive(|sender| load_ktx2(sender, String::from("ground"), ktx2, true).unwrap());
+        assert_eq!((texture.width, texture.height), (8, 8));
+        assert_eq!(texture.format, TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!(texture.data, levels[0]);
+        assert_eq!(texture.levels, &levels[1..]);
+    }
+
+    #[test]
+    fn dds_levels() {
+        let mut dds = Dds::new_dxgi(ddsfile::NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: DxgiFormat::BC3_UNorm,
+            mipmap_levels: Some(4),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+            alpha_mode: ddsfile::AlphaMode::Unknown,
+        })
+        .unwrap();
+        for (i, value) in dds.get_mut_data(0).unwrap().iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        let mut data = Vec::new();
+        dds.write(&mut data).unwrap();
+
+        let texture = receive(|sender| load_dds(sender, String::from("rock"), data).unwrap());
+        assert_eq!((texture.width, texture.height), (8, 8));
+        assert_eq!(texture.format, TextureFormat::Bc3RgbaUnorm);
+        // 4 blocks of 16 bytes on the base level and 1 block on the smaller ones
+        assert_eq!(texture.data.len(), 64);
+        assert_eq!(texture.levels.len(), 3);
+        assert!(texture.levels.iter().all(|level| level.len() == 16));
+        assert_eq!(texture.levels[0][0], 64);
+        assert_eq!(texture.levels[2][0], 96);
+
+        dds.header.mip_map_count = Some(0);
+        let mut data = Vec::new();
+        dds.write(&mut data).unwrap();
+
+        let texture = receive(|sender| load_dds(sender, String::from("rock"), data).unwrap());
+        assert_eq!(texture.data.len(), 64);
+        assert!(texture.levels.is_empty());
+    }
+
+    fn f16_pixel(data: &[u8], index: usize) -> [f32; 4] {
+        let mut pixel = [0.0; 4];
+        for (i, value) in pixel.iter_mut().enumerate() {
+            let offset = (index * 4 + i) * 2;
+            *value = f16::from_le_bytes([data[offset], data[offset + 1]]).to_f32();
+        }
+        pixel
+    }
+
+    #[test]
+    fn hdr_to_float() {
+        let pixels = [image::Rgb([4.0, 0.5, 0.0]), image::Rgb([0.0, 1.0, 16.0])];
+        let mut data = Vec::new();
+        image::codecs::hdr::HdrEncoder::new(&mut data)
+            .encode(&pixels, 2, 1)
+            .unwrap();
+
+        let texture = receive(|sender| load_hdr(sender, String::from("sky"), data).unwrap());
+        assert_eq!((texture.width, texture.height), (2, 1));
+        assert_eq!(texture.format, TextureFormat::Rgba16Float);
+        assert_eq!(f16_pixel(&texture.data, 0), [4.0, 0.5, 0.0, 1.0]);
+        assert_eq!(f16_pixel(&texture.data, 1), [0.0, 1.0, 16.0, 1.0]);
+    }
+
+    #[test]
+    fn exr_to_float() {
+        use exr::prelude::{Image, SpecificChannels, Vec2, WritableImage};
+
+        let channels = SpecificChannels::rgba(|position: Vec2<usize>| {
+            let value = position.x() as f32 + 2.0 * position.y() as f32;
+            (value, 0.25, 8.0, 0.5)
+        });
+        let mut data = Cursor::new(Vec::new());
+        Image::from_channels((2, 2), channels)
+            .write()
+            .to_buffered(&mut data)
+            .unwrap();
+
+        let texture =
+            receive(|sender| load_exr(sender, String::from("light"), data.into_inner()).unwrap());
+        assert_eq!((texture.width, texture.height), (2, 2));
+        assert_eq!(texture.format, TextureFormat::Rgba16Float);
+        assert_eq!(f16_pixel(&texture.data, 1), [1.0, 0.25, 8.0, 0.5]);
+        assert_eq!(f16_pixel(&texture.data, 2), [2.0, 0.25, 8.0, 0.5]);
+    }
+}
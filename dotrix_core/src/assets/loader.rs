@@ -1,17 +1,28 @@
 //! Assets loader
 use std::{
-    fs::File,
+    any::Any,
+    collections::HashMap,
     path::PathBuf,
-    sync::{mpsc, Arc, Mutex},
+    sync::{mpsc, Arc, Mutex, RwLock},
     thread,
 };
 
 use log::error;
 
 use super::{
-    animation::Animation, load_gltf::load_gltf, mesh::Mesh, shader::Shader, skin::Skin,
+    animation::Animation,
+    importer::{AssetImporter, Files},
+    material::Material,
+    mesh::Mesh,
+    pack::Pack,
+    prefab::Prefab,
+    preprocessor::{Preprocessor, PreprocessorError},
+    resource::{ImportSettings, Resource},
+    shader::Shader,
+    skin::Skin,
     texture::Texture,
 };
+use crate::id::Id;
 
 /// Asset loading task
 pub struct Task {
@@ -19,16 +30,36 @@ pub struct Task {
     pub path: PathBuf,
     /// Asset name
     pub name: String,
+    /// [`Id`] of the imported [`Resource`]
+    pub resource: Id<Resource>,
+    /// Import settings of the [`Resource`]
+    pub settings: ImportSettings,
 }
 
+/// Registered importers by file extensions
+pub(crate) type Importers = Arc<RwLock<HashMap<String, Arc<dyn AssetImporter>>>>;
+
+/// Mounted asset packs
+pub(crate) type Packs = Arc<RwLock<Vec<Arc<Pack>>>>;
+
 /// Imported asset data
-pub struct Asset<T> {
+pub struct Asset<T: ?Sized> {
     /// Asset name
     pub name: String,
     /// Asset data
     pub asset: Box<T>,
 }
 
+/// Result of a [`Resource`] import
+pub struct Imported {
+    /// [`Id`] of the imported [`Resource`]
+    pub resource: Id<Resource>,
+    /// Names of assets imported from the [`Resource`]
+    pub assets: Vec<String>,
+    /// Import result
+    pub result: Result<(), ImportError>,
+}
+
 /// Loading request
 pub enum Request {
     /// Resource import request
@@ -43,12 +74,37 @@ pub enum Response {
     Animation(Asset<Animation>),
     /// Texture asset loaded
     Texture(Asset<Texture>),
+    /// Material asset loaded
+    Material(Asset<Material>),
     /// Mesh asset loaded
     Mesh(Asset<Mesh>),
+    /// Prefab asset loaded
+    Prefab(Asset<Prefab>),
     /// Sshader asset loaded
     Shader(Asset<Shader>),
     /// Skin asset loaded
     Skin(Asset<Skin>),
+    /// Custom asset loaded
+    Custom(Asset<dyn Any + Send + Sync>),
+    /// Resource import finished, all its assets were sent before
+    Imported(Imported),
+}
+
+impl Response {
+    /// Returns name of the loaded asset
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Response::Animation(asset) => Some(&asset.name),
+            Response::Texture(asset) => Some(&asset.name),
+            Response::Material(asset) => Some(&asset.name),
+            Response::Mesh(asset) => Some(&asset.name),
+            Response::Prefab(asset) => Some(&asset.name),
+            Response::Shader(asset) => Some(&asset.name),
+            Response::Skin(asset) => Some(&asset.name),
+            Response::Custom(asset) => Some(&asset.name),
+            Response::Imported(_) => None,
+        }
+    }
 }
 
 /// Asset loader thread
@@ -67,10 +123,24 @@ pub enum ImportError {
     ImageDecode(image::ImageError),
     /// Error occuerd during GLTF decoding
     GltfDecode(gltf::Error),
+    /// Error occured during KTX2 decoding
+    Ktx2Decode(ktx2::ParseError),
+    /// Error occured during DDS decoding
+    DdsDecode(ddsfile::Error),
+    /// Error occured during OpenEXR decoding
+    ExrDecode(exr::error::Error),
     /// Feature is not implemented
     NotImplemented(&'static str, Option<String>),
     /// It seems that source data were corrupted
     Corruption(&'static str),
+    /// Error reported by a custom importer
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+    /// Resource was imported, but has no asset of the requested type
+    MissingAsset(&'static str),
+    /// Error occured during shader preprocessing
+    Preprocessor(PreprocessorError),
+    /// Import result can't be delivered, because the [`super::Assets`] service was dropped
+    Cancelled,
 }
 
 impl Loader {
@@ -79,17 +149,43 @@ impl Loader {
         id: usize,
         receiver: Arc<Mutex<mpsc::Receiver<Request>>>,
         sender: Arc<Mutex<mpsc::Sender<Response>>>,
+        importers: Importers,
+        packs: Packs,
     ) -> Self {
         let thread = thread::spawn(move || loop {
             let request = receiver.lock().unwrap().recv().unwrap();
             match request {
                 Request::Import(task) => {
-                    if let Err(e) = import_resource(&task, &sender) {
+                    // assets are collected first, so the resource is reported with all of them
+                    let (task_sender, task_receiver) = mpsc::channel();
+                    let result = import_resource(
+                        &task,
+                        &Arc::new(Mutex::new(task_sender)),
+                        &importers,
+                        &packs,
+                    );
+                    if let Err(e) = result.as_ref() {
                         error!(
                             "[{}] Resource import from `{:?}` failed: \n\t{:?}",
                             id, task.path, e
                         );
                     }
+
+                    let sender = sender.lock().unwrap();
+                    let mut assets = Vec::new();
+                    for response in task_receiver.try_iter() {
+                        if let Some(name) = response.name() {
+                            assets.push(String::from(name));
+                        }
+                        sender.send(response).unwrap();
+                    }
+                    sender
+                        .send(Response::Imported(Imported {
+                            resource: task.resource,
+                            assets,
+                            result,
+                        }))
+                        .unwrap();
                 }
                 Request::Terminate => break,
             }
@@ -111,31 +207,29 @@ impl Loader {
 fn import_resource(
     task: &Task,
     sender: &Arc<Mutex<mpsc::Sender<Response>>>,
+    importers: &Importers,
+    packs: &Packs,
 ) -> Result<(), ImportError> {
-    use std::io::Read;
-
     let name = String::from(task.path.file_stem().unwrap().to_str().unwrap());
 
-    let mut file = File::open(&task.path)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-
-    if let Some(extension) = task.path.extension() {
-        let extension = extension.to_str().unwrap();
-        match extension {
-            "png" | "jpg" | "jpeg" | "bmp" => load_image(
-                sender,
-                name,
-                buffer,
-                image::ImageFormat::from_extension(extension).unwrap(),
-            ),
-            "wgsl" => load_wgsl(sender, name, buffer),
-            "gltf" | "gltb" => load_gltf(sender, name, buffer, &task.path),
-            _ => Err(ImportError::NotImplemented("extension", None)),
-        }
-    } else {
-        Err(ImportError::NotImplemented("file without extension", None))
-    }
+    let extension = task
+        .path
+        .extension()
+        .map(|extension| extension.to_str().unwrap())
+        .ok_or(ImportError::NotImplemented("file without extension", None))?;
+
+    let importer = importers
+        .read()
+        .unwrap()
+        .get(extension)
+        .cloned()
+        .ok_or_else(|| ImportError::NotImplemented("extension", Some(String::from(extension))))?;
+
+    let packs = packs.read().unwrap().clone();
+    let files = Files::new(&task.path, &packs).with_settings(task.settings);
+    let buffer = files.read(&task.path)?;
+
+    importer.import(sender, name, buffer, &files)
 }
 
 pub(crate) fn load_image(
@@ -143,21 +237,27 @@ pub(crate) fn load_image(
     name: String,
     data: Vec<u8>,
     format: image::ImageFormat,
+    mipmaps: bool,
 ) -> Result<(), ImportError> {
     let image = image::load_from_memory_with_format(data.as_slice(), format)?;
     let image = image.into_rgba8();
 
     let (width, height) = image.dimensions();
 
+    let mut texture = Texture {
+        width,
+        height,
+        depth: 1,
+        data: image.into_vec(),
+        ..Default::default()
+    };
+    if mipmaps {
+        texture.generate_mipmaps();
+    }
+
     let texture = Asset {
         name,
-        asset: Box::new(Texture {
-            width,
-            height,
-            depth: 1,
-            data: image.into_vec(),
-            ..Default::default()
-        }),
+        asset: Box::new(texture),
     };
     sender
         .lock()
@@ -171,14 +271,12 @@ pub(crate) fn load_wgsl(
     sender: &Arc<Mutex<mpsc::Sender<Response>>>,
     name: String,
     data: Vec<u8>,
+    preprocessor: &Preprocessor,
 ) -> Result<(), ImportError> {
+    let shader = Shader::from_wgsl(&name, &String::from_utf8_lossy(&data), preprocessor)?;
     let shader = Asset {
-        name: String::from(&name),
-        asset: Box::new(Shader {
-            name,
-            code: String::from_utf8_lossy(&data).to_string(),
-            ..Default::default()
-        }),
+        name,
+        asset: Box::new(shader),
     };
     sender
         .lock()
@@ -190,6 +288,29 @@ pub(crate) fn load_wgsl(
 
 impl std::error::Error for ImportError {}
 
+impl ImportError {
+    /// Returns a copy of the error
+    ///
+    /// Errors of other crates can't be cloned, so they are copied as [`ImportError::Custom`]
+    /// keeping the message.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            ImportError::Base64Decode(err) => ImportError::Base64Decode(err.clone()),
+            ImportError::FileRead(err) => {
+                ImportError::FileRead(std::io::Error::new(err.kind(), err.to_string()))
+            }
+            ImportError::NotImplemented(feature, variant) => {
+                ImportError::NotImplemented(feature, variant.clone())
+            }
+            ImportError::Corruption(err) => ImportError::Corruption(err),
+            ImportError::MissingAsset(asset_type) => ImportError::MissingAsset(asset_type),
+            ImportError::Preprocessor(err) => ImportError::Preprocessor(err.clone()),
+            ImportError::Cancelled => ImportError::Cancelled,
+            err => ImportError::Custom(err.to_string().into()),
+        }
+    }
+}
+
 impl std::fmt::Display for ImportError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -197,16 +318,31 @@ impl std::fmt::Display for ImportError {
             ImportError::FileRead(err) => write!(f, "Can't read file ({:?})", err),
             ImportError::ImageDecode(err) => write!(f, "Can't decode image ({:?})", err),
             ImportError::GltfDecode(err) => write!(f, "Can't decode GLTF ({:?})", err),
+            ImportError::Ktx2Decode(err) => write!(f, "Can't decode KTX2 ({:?})", err),
+            ImportError::DdsDecode(err) => write!(f, "Can't decode DDS ({:?})", err),
+            ImportError::ExrDecode(err) => write!(f, "Can't decode OpenEXR ({:?})", err),
             ImportError::NotImplemented(feature, variant) => write!(
                 f,
                 "Not implemented support for the {:?} ({:?})",
                 feature, variant
             ),
             ImportError::Corruption(err) => write!(f, "File could be corrupted ({:?})", err),
+            ImportError::Custom(err) => write!(f, "Import failed ({})", err),
+            ImportError::MissingAsset(asset_type) => {
+                write!(f, "Resource has no asset of type `{}`", asset_type)
+            }
+            ImportError::Preprocessor(err) => write!(f, "Can't preprocess shader ({})", err),
+            ImportError::Cancelled => write!(f, "Import was cancelled, assets were dropped"),
         }
     }
 }
 
+impl From<PreprocessorError> for ImportError {
+    fn from(err: PreprocessorError) -> Self {
+        ImportError::Preprocessor(err)
+    }
+}
+
 impl From<std::io::Error> for ImportError {
     fn from(err: std::io::Error) -> Self {
         ImportError::FileRead(err)
@@ -230,3 +366,21 @@ impl From<gltf::Error> for ImportError {
         ImportError::GltfDecode(err)
     }
 }
+
+impl From<ktx2::ParseError> for ImportError {
+    fn from(err: ktx2::ParseError) -> Self {
+        ImportError::Ktx2Decode(err)
+    }
+}
+
+impl From<ddsfile::Error> for ImportError {
+    fn from(err: ddsfile::Error) -> Self {
+        ImportError::DdsDecode(err)
+    }
+}
+
+impl From<exr::error::Error> for ImportError {
+    fn from(err: exr::error::Error) -> Self {
+        ImportError::ExrDecode(err)
+    }
+}
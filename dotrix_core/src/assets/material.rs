@@ -0,0 +1,52 @@
+//! Material asset
+use crate::color::Color;
+
+/// Material asset
+///
+/// Physically based material properties, as they were imported from a model file. Textures are
+/// referenced by names of [`super::Texture`] assets. Metallic, roughness and ambient occlusion
+/// textures are single channel maps: the value is stored in all channels of a pixel and
+/// respective factors are already applied.
+#[derive(Debug, Clone)]
+pub struct Material {
+    /// Base color factor
+    pub albedo: Color,
+    /// Name of a base color texture asset
+    pub texture: Option<String>,
+    /// Metallic factor
+    pub metallic: f32,
+    /// Name of a metallic texture asset
+    pub metallic_texture: Option<String>,
+    /// Roughness factor
+    pub roughness: f32,
+    /// Name of a roughness texture asset
+    pub roughness_texture: Option<String>,
+    /// Ambient occlusion
+    pub ao: f32,
+    /// Name of an ambient occlusion texture asset
+    pub ao_texture: Option<String>,
+    /// Name of a normal map asset
+    pub normal_texture: Option<String>,
+    /// Emissive color factor
+    pub emissive: Color,
+    /// Name of an emissive texture asset
+    pub emissive_texture: Option<String>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            albedo: Color::white(),
+            texture: None,
+            metallic: 1.0,
+            metallic_texture: None,
+            roughness: 1.0,
+            roughness_texture: None,
+            ao: 1.0,
+            ao_texture: None,
+            normal_texture: None,
+            emissive: Color::black(),
+            emissive_texture: None,
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Asset packs
+//!
+//! Pack is an indexed archive of asset files. It starts with a header, followed by an index of
+//! files and their data. All numbers are little-endian:
+//!
+//! ```text
+//! "DOTRIXPK"                            magic, 8 bytes
+//! version: u32
+//! entries: u32
+//! { name_len: u32, name: [u8], offset: u64, size: u64 } * entries
+//! files data
+//! ```
+//!
+//! Names of files are relative paths with `/` separators. Packs can be created by
+//! [`PackWriter`] or `dotrix-pack` command-line tool.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const MAGIC: &[u8; 8] = b"DOTRIXPK";
+const VERSION: u32 = 1;
+
+/// Location of a file inside of a [`Pack`]
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    offset: u64,
+    size: u64,
+}
+
+/// Mounted asset pack
+pub struct Pack {
+    path: PathBuf,
+    mount: PathBuf,
+    entries: HashMap<String, Entry>,
+}
+
+impl Pack {
+    /// Opens a pack file and mounts it to the directory
+    ///
+    /// Files of the pack are resolved as if they were stored in the mount directory.
+    pub fn open(path: &Path, mount: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let length = file.metadata()?.len();
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not an asset pack"));
+        }
+        if read_u32(&mut file)? != VERSION {
+            return Err(invalid_data("unsupported asset pack version"));
+        }
+
+        // header fields are not trusted, so sizes are checked against the file length and
+        // buffers grow only with the data actually read
+        let count = read_u32(&mut file)?;
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            let name_len = read_u32(&mut file)?;
+            let name = read_exact(&mut file, name_len as u64)?;
+            let name = String::from_utf8(name).map_err(|_| invalid_data("invalid file name"))?;
+            let offset = read_u64(&mut file)?;
+            let size = read_u64(&mut file)?;
+            if offset
+                .checked_add(size)
+                .map(|end| end > length)
+                .unwrap_or(true)
+            {
+                return Err(invalid_data("file is out of the asset pack"));
+            }
+            entries.insert(name, Entry { offset, size });
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            mount: normalize(mount),
+            entries,
+        })
+    }
+
+    /// Returns path of the pack file
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Returns true if the pack contains a file by its path in the mount directory
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entry_name(path)
+            .map(|name| self.entries.contains_key(&name))
+            .unwrap_or(false)
+    }
+
+    /// Returns iterator over names of files in the pack
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|name| name.as_str())
+    }
+
+    /// Reads a file by its path in the mount directory
+    ///
+    /// Returns `None` if the file is not in the pack
+    pub fn read(&self, path: &Path) -> Option<io::Result<Vec<u8>>> {
+        let entry = self
+            .entry_name(path)
+            .and_then(|name| self.entries.get(&name))
+            .copied()?;
+
+        Some(File::open(&self.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(entry.offset))?;
+            read_exact(&mut file, entry.size)
+        }))
+    }
+
+    fn entry_name(&self, path: &Path) -> Option<String> {
+        normalize(path)
+            .strip_prefix(&self.mount)
+            .ok()
+            .and_then(|relative| relative.to_str())
+            .map(|relative| relative.replace('\\', "/"))
+    }
+}
+
+/// Asset pack writer
+#[derive(Default)]
+pub struct PackWriter {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl PackWriter {
+    /// Constructs new empty [`PackWriter`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file to the pack
+    pub fn add(&mut self, name: &str, data: Vec<u8>) {
+        self.files.insert(name.replace('\\', "/"), data);
+    }
+
+    /// Adds all files of the directory and its subdirectories to the pack
+    pub fn add_directory(&mut self, directory: &Path) -> io::Result<()> {
+        self.add_files(directory, directory)
+    }
+
+    fn add_files(&mut self, root: &Path, directory: &Path) -> io::Result<()> {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.add_files(root, &path)?;
+            } else {
+                let name = path
+                    .strip_prefix(root)
+                    .ok()
+                    .and_then(|name| name.to_str())
+                    .ok_or_else(|| invalid_data("invalid file name"))?
+                    .to_string();
+                self.add(&name, std::fs::read(&path)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns number of files in the pack
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns true if the pack has no files
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Writes the pack
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let index_size = self
+            .files
+            .keys()
+            .map(|name| 4 + name.len() as u64 + 8 + 8)
+            .sum::<u64>();
+        let mut offset = MAGIC.len() as u64 + 4 + 4 + index_size;
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.files.len() as u32).to_le_bytes())?;
+
+        for (name, data) in self.files.iter() {
+            writer.write_all(&(name.len() as u32).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(data.len() as u64).to_le_bytes())?;
+            offset += data.len() as u64;
+        }
+
+        for data in self.files.values() {
+            writer.write_all(data)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves `.` and `..` components of the path without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+    result
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads exactly `size` bytes, growing the buffer with the data read
+fn read_exact<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(size).read_to_end(&mut data)?;
+    if (data.len() as u64) < size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(data)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn write_and_read() {
+        let dir = TempDir::new("pack");
+        let path = dir.join("assets.pack");
+
+        let mut writer = PackWriter::new();
+        writer.add("models/car.gltf", vec![1, 2, 3]);
+        writer.add("models\\car.bin", vec![4, 5]);
+        writer.add("shader.wgsl", Vec::new());
+        writer.write(&mut File::create(&path).unwrap()).unwrap();
+
+        let mount = Path::new("/game/assets");
+        let pack = Pack::open(&path, mount).unwrap();
+        assert_eq!(pack.names().count(), 3);
+        assert!(pack.contains(&mount.join("models/car.gltf")));
+        assert!(!pack.contains(Path::new("models/car.gltf")));
+        assert!(!pack.contains(&mount.join("missing.png")));
+
+        let read = |path: &str| pack.read(&mount.join(path)).map(|data| data.unwrap());
+        assert_eq!(read("models/car.gltf"), Some(vec![1, 2, 3]));
+        assert_eq!(read("models/../models/./car.bin"), Some(vec![4, 5]));
+        assert_eq!(read("shader.wgsl"), Some(Vec::new()));
+        assert_eq!(read("missing.png"), None);
+
+        std::fs::write(&path, b"NOTAPACK").unwrap();
+        assert!(Pack::open(&path, mount).is_err());
+
+        // corrupted sizes are rejected without allocating them
+        let header = |fields: &[&[u8]]| {
+            let mut data = Vec::from(&MAGIC[..]);
+            data.extend_from_slice(&VERSION.to_le_bytes());
+            fields
+                .iter()
+                .for_each(|field| data.extend_from_slice(field));
+            std::fs::write(&path, data).unwrap();
+            Pack::open(&path, mount).map(|_| ()).unwrap_err().kind()
+        };
+        assert_eq!(
+            header(&[&u32::MAX.to_le_bytes()]),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            header(&[&1u32.to_le_bytes(), &u32::MAX.to_le_bytes(), b"name"]),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            header(&[
+                &1u32.to_le_bytes(),
+                &1u32.to_le_bytes(),
+                b"a",
+                &0u64.to_le_bytes(),
+                &u64::MAX.to_le_bytes(),
+            ]),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Prefab asset
+use crate::transform::Transform;
+
+/// Index of a node inside of the [`Prefab`]
+pub type NodeIndex = usize;
+
+/// Prefab asset
+///
+/// Keeps the node graph of an imported scene: local transformations, hierarchy and bindings of
+/// meshes, skins and materials. Assets are referenced by names, so the prefab stays valid even
+/// if some of them were not loaded yet.
+#[derive(Debug, Clone, Default)]
+pub struct Prefab {
+    /// Nodes of the prefab, parents always precede their children
+    pub nodes: Vec<Node>,
+}
+
+/// Prefab node
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// Name of the node
+    pub name: Option<String>,
+    /// Index of the parent node
+    pub parent: Option<NodeIndex>,
+    /// Local transformation of the node
+    pub transform: Transform,
+    /// Name of a skin asset
+    pub skin: Option<String>,
+    /// Primitives of the node mesh
+    pub primitives: Vec<Primitive>,
+}
+
+/// Mesh and material binding of a [`Node`]
+#[derive(Debug, Clone)]
+pub struct Primitive {
+    /// Name of a mesh asset
+    pub mesh: String,
+    /// Name of a material asset
+    pub material: Option<String>,
+}
+
+impl Prefab {
+    /// Adds a node to the [`Prefab`] and returns its index
+    pub fn add(&mut self, node: Node) -> NodeIndex {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Returns iterator over root nodes of the [`Prefab`]
+    pub fn roots(&self) -> impl Iterator<Item = (NodeIndex, &Node)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.parent.is_none())
+    }
+
+    /// Returns iterator over children of the node
+    pub fn children(&self, index: NodeIndex) -> impl Iterator<Item = (NodeIndex, &Node)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == Some(index))
+    }
+}
@@ -0,0 +1,342 @@
+//! WGSL preprocessor
+//!
+//! Preprocessor composes shaders from registered modules and feature permutations. Directives
+//! must be the only content of their lines:
+//!
+//! ```text
+//! #include "module"      inserts code of the registered module, once per shader
+//! #define NAME value     defines a name, value is optional
+//! #undef NAME            removes the definition
+//! #ifdef NAME            keeps following lines, if the name is defined
+//! #ifndef NAME           keeps following lines, if the name is not defined
+//! #else                  inverts the condition
+//! #endif                 ends the conditional block
+//! ```
+//!
+//! Values of definitions are substituted into the code with `{{ NAME }}` templates, for
+//! example to set indices of bind groups:
+//!
+//! ```text
+//! [[group({{ LIGHTS_BIND_GROUP }}), binding({{ LIGHTS_BINDING }})]]
+//! var<uniform> u_light: Light;
+//! ```
+use std::collections::{HashMap, HashSet};
+
+/// Preprocessor error with the location in the original file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessorError {
+    /// Name of the shader or of the module
+    pub file: String,
+    /// Number of the line, starting from 1
+    pub line: usize,
+    /// Error message
+    pub message: String,
+}
+
+impl std::error::Error for PreprocessorError {}
+
+impl std::fmt::Display for PreprocessorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// WGSL preprocessor
+///
+/// ```
+/// use dotrix_core::assets::Preprocessor;
+///
+/// let preprocessor = Preprocessor::new()
+///     .with_module("math", "let PI: f32 = 3.14159;")
+///     .with_define("BIND_GROUP", 1);
+///
+/// let code = preprocessor
+///     .process("shader.wgsl", "#include \"math\"\n[[group({{ BIND_GROUP }})]]")
+///     .unwrap();
+/// assert_eq!(code, "let PI: f32 = 3.14159;\n[[group(1)]]\n");
+/// ```
+#[derive(Default, Clone)]
+pub struct Preprocessor {
+    modules: HashMap<String, String>,
+    defines: HashMap<String, String>,
+}
+
+/// State of a conditional block
+struct Condition {
+    /// Line of the opening directive
+    line: usize,
+    /// Lines of the branch are kept
+    active: bool,
+    /// Lines of the enclosing block are kept
+    parent: bool,
+    /// `#else` was already met
+    inverted: bool,
+}
+
+/// State of a processed shader
+struct State {
+    defines: HashMap<String, String>,
+    included: HashSet<String>,
+    output: String,
+}
+
+impl Preprocessor {
+    /// Constructs new [`Preprocessor`] without modules and definitions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a module to be included
+    pub fn add_module(&mut self, name: &str, code: &str) {
+        self.modules.insert(String::from(name), String::from(code));
+    }
+
+    /// Registers a module to be included
+    #[must_use]
+    pub fn with_module(mut self, name: &str, code: &str) -> Self {
+        self.add_module(name, code);
+        self
+    }
+
+    /// Defines a name for all processed shaders
+    pub fn define<T: ToString>(&mut self, name: &str, value: T) {
+        self.defines.insert(String::from(name), value.to_string());
+    }
+
+    /// Defines a name for all processed shaders
+    #[must_use]
+    pub fn with_define<T: ToString>(mut self, name: &str, value: T) -> Self {
+        self.define(name, value);
+        self
+    }
+
+    /// Removes the definition
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    /// Returns true if a module with the name was registered
+    pub fn has_module(&self, name: &str) -> bool {
+        self.modules.contains_key(name)
+    }
+
+    /// Processes the shader source code
+    ///
+    /// The `file` name is used to report errors only.
+    pub fn process(&self, file: &str, source: &str) -> Result<String, PreprocessorError> {
+        let mut state = State {
+            defines: self.defines.clone(),
+            included: HashSet::new(),
+            output: String::with_capacity(source.len()),
+        };
+        self.process_file(&mut state, file, source)?;
+        Ok(state.output)
+    }
+
+    fn process_file(
+        &self,
+        state: &mut State,
+        file: &str,
+        source: &str,
+    ) -> Result<(), PreprocessorError> {
+        let mut conditions: Vec<Condition> = Vec::new();
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            let error = |message: String| PreprocessorError {
+                file: String::from(file),
+                line,
+                message,
+            };
+            let active = conditions.last().map(|c| c.active).unwrap_or(true);
+
+            let directive = match text.trim().strip_prefix('#') {
+                Some(directive) => directive,
+                None => {
+                    if active {
+                        substitute(&mut state.output, text, &state.defines).map_err(error)?;
+                        state.output.push('\n');
+                    }
+                    continue;
+                }
+            };
+
+            let (keyword, argument) = directive
+                .split_once(char::is_whitespace)
+                .map(|(keyword, argument)| (keyword, argument.trim()))
+                .unwrap_or((directive, ""));
+
+            match keyword {
+                "ifdef" | "ifndef" => {
+                    let name = identifier(argument).map_err(error)?;
+                    let defined = state.defines.contains_key(name);
+                    conditions.push(Condition {
+                        line,
+                        active: active && defined == (keyword == "ifdef"),
+                        parent: active,
+                        inverted: false,
+                    });
+                }
+                "else" => {
+                    let condition = conditions
+                        .last_mut()
+                        .filter(|condition| !condition.inverted)
+                        .ok_or_else(|| error(String::from("`#else` without `#ifdef`")))?;
+                    condition.active = condition.parent && !condition.active;
+                    condition.inverted = true;
+                }
+                "endif" => {
+                    conditions
+                        .pop()
+                        .ok_or_else(|| error(String::from("`#endif` without `#ifdef`")))?;
+                }
+                // other directives are ignored in the inactive blocks
+                _ if !active => (),
+                "define" => {
+                    let (name, value) = argument
+                        .split_once(char::is_whitespace)
+                        .map(|(name, value)| (name, value.trim()))
+                        .unwrap_or((argument, ""));
+                    let name = identifier(name).map_err(error)?;
+                    state
+                        .defines
+                        .insert(String::from(name), String::from(value));
+                }
+                "undef" => {
+                    let name = identifier(argument).map_err(error)?;
+                    state.defines.remove(name);
+                }
+                "include" => {
+                    let name = argument
+                        .strip_prefix('"')
+                        .and_then(|name| name.strip_suffix('"'))
+                        .or_else(|| {
+                            argument
+                                .strip_prefix('<')
+                                .and_then(|name| name.strip_suffix('>'))
+                        })
+                        .ok_or_else(|| error(format!("invalid include `{}`", argument)))?;
+                    let code = self
+                        .modules
+                        .get(name)
+                        .ok_or_else(|| error(format!("module `{}` is not registered", name)))?;
+                    // modules are included once, that also breaks include cycles
+                    if state.included.insert(String::from(name)) {
+                        self.process_file(state, name, code)?;
+                    }
+                }
+                _ => return Err(error(format!("unknown directive `#{}`", keyword))),
+            }
+        }
+
+        match conditions.pop() {
+            Some(condition) => Err(PreprocessorError {
+                file: String::from(file),
+                line: condition.line,
+                message: String::from("`#ifdef` without `#endif`"),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Validates name of a definition
+fn identifier(name: &str) -> Result<&str, String> {
+    let valid = name
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!("invalid name `{}`", name))
+    }
+}
+
+/// Writes the line to the output, replacing `{{ NAME }}` templates by their values
+fn substitute(
+    output: &mut String,
+    text: &str,
+    defines: &HashMap<String, String>,
+) -> Result<(), String> {
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| String::from("unterminated `{{`"))?;
+        let name = rest[start + 2..start + end].trim();
+        let value = defines
+            .get(name)
+            .ok_or_else(|| format!("`{}` is not defined", name))?;
+        output.push_str(&rest[..start]);
+        output.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_and_conditionals() {
+        let preprocessor = Preprocessor::new()
+            .with_module("light", "#include \"math\"\nfn light() {}")
+            .with_module("math", "#include \"light\"\nlet SCALE: f32 = {{ SCALE }};")
+            .with_define("SCALE", 2.5)
+            .with_define("GROUP", 0);
+
+        let source = "\
+#include \"light\"
+#include <math>
+#define SKINNED
+#ifdef SKINNED
+skinned({{ GROUP }});
+#ifndef SHADOWS
+no_shadows();
+#else
+shadows();
+#endif
+#else
+static();
+#endif
+#undef SKINNED
+#ifdef SKINNED
+skinned();
+#endif";
+
+        let code = preprocessor.process("shader.wgsl", source).unwrap();
+        assert_eq!(
+            code,
+            "let SCALE: f32 = 2.5;\nfn light() {}\nskinned(0);\nno_shadows();\n"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let preprocessor = Preprocessor::new().with_module("broken", "fn f() {}\n#else");
+        let error = |source| preprocessor.process("shader.wgsl", source).unwrap_err();
+
+        assert_eq!(
+            error("\n#include \"broken\"").to_string(),
+            "broken:2: `#else` without `#ifdef`"
+        );
+        assert_eq!(
+            error("\n\n#include \"missing\""),
+            PreprocessorError {
+                file: String::from("shader.wgsl"),
+                line: 3,
+                message: String::from("module `missing` is not registered"),
+            }
+        );
+        assert_eq!(error("#ifdef A\n#endif\n#ifdef B\n").line, 3);
+        assert_eq!(error("\n[[group({{ GROUP }})]]").line, 2);
+        assert_eq!(error("#define 1A").line, 1);
+        assert_eq!(error("#pragma once").line, 1);
+    }
+}
@@ -1,15 +1,67 @@
 //! Generic asset resource
+use super::ImportError;
+
+/// Import status of a [`Resource`]
+#[derive(Debug)]
+pub enum Status {
+    /// Resource is being imported
+    Pending,
+    /// Resource was imported and all its assets were stored
+    Loaded,
+    /// Resource import failed
+    Failed(ImportError),
+}
+
+/// Settings of a [`Resource`] import
+///
+/// Settings are kept by the [`Resource`] and applied again, when it is reloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSettings {
+    /// Generate mip levels of imported textures, that have none
+    pub mipmaps: bool,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self { mipmaps: true }
+    }
+}
+
+impl ImportSettings {
+    /// Enables or disables generation of mip levels, e.g. for UI, font or pixel-art textures
+    #[must_use]
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+}
 
 /// Data structure representing an asset file
 pub struct Resource {
     name: String,
     path: String,
+    settings: ImportSettings,
+    status: Status,
+    assets: Vec<String>,
 }
 
 impl Resource {
     /// Constructs new resource
     pub fn new(name: String, path: String) -> Self {
-        Self { name, path }
+        Self {
+            name,
+            path,
+            settings: ImportSettings::default(),
+            status: Status::Pending,
+            assets: Vec::new(),
+        }
+    }
+
+    /// Sets import settings of the resource
+    #[must_use]
+    pub fn with_settings(mut self, settings: ImportSettings) -> Self {
+        self.settings = settings;
+        self
     }
 
     /// Returns the [`Resource`] path
@@ -21,4 +73,39 @@ impl Resource {
     pub fn name(&self) -> &String {
         &self.name
     }
+
+    /// Returns import settings of the [`Resource`]
+    pub fn settings(&self) -> ImportSettings {
+        self.settings
+    }
+
+    /// Returns import status of the [`Resource`]
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Returns names of assets imported from the [`Resource`]
+    pub fn assets(&self) -> &[String] {
+        self.assets.as_slice()
+    }
+
+    /// Returns true if the [`Resource`] import was finished, successfully or not
+    pub fn is_ready(&self) -> bool {
+        !matches!(self.status, Status::Pending)
+    }
+
+    /// Marks the [`Resource`] as being imported again
+    pub(crate) fn pending(&mut self) {
+        self.status = Status::Pending;
+        self.assets.clear();
+    }
+
+    /// Sets the import result of the [`Resource`]
+    pub(crate) fn imported(&mut self, assets: Vec<String>, result: Result<(), ImportError>) {
+        self.assets = assets;
+        self.status = match result {
+            Ok(()) => Status::Loaded,
+            Err(error) => Status::Failed(error),
+        };
+    }
 }
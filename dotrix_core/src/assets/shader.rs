@@ -1,4 +1,5 @@
 //! Shader Asset
+use super::preprocessor::{Preprocessor, PreprocessorError};
 use crate::renderer::{Renderer, ShaderModule};
 
 /// Shader Asset
@@ -13,6 +14,19 @@ pub struct Shader {
 }
 
 impl Shader {
+    /// Constructs the shader from WGSL source code, processed by the [`Preprocessor`]
+    pub fn from_wgsl(
+        name: &str,
+        source: &str,
+        preprocessor: &Preprocessor,
+    ) -> Result<Self, PreprocessorError> {
+        Ok(Self {
+            name: String::from(name),
+            code: preprocessor.process(name, source)?,
+            ..Default::default()
+        })
+    }
+
     /// Loads the shader to GPU
     pub fn load(&mut self, renderer: &Renderer) {
         if !self.module.loaded() {
@@ -0,0 +1,27 @@
+//! Storage of custom assets
+use std::{any::Any, collections::HashMap};
+
+use super::importer::CustomAsset;
+use crate::id::Id;
+
+/// Type erased map of custom assets
+pub trait Storage: Send + Sync {
+    /// Stores the asset, if it is of the map type
+    fn insert(&mut self, id: u64, asset: Box<dyn Any + Send + Sync>);
+    fn as_any_ref(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: CustomAsset> Storage for HashMap<Id<T>, T> {
+    fn insert(&mut self, id: u64, asset: Box<dyn Any + Send + Sync>) {
+        if let Ok(asset) = asset.downcast::<T>() {
+            HashMap::insert(self, Id::new(id), *asset);
+        }
+    }
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
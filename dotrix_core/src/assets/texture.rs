@@ -1,5 +1,7 @@
 //! Texture asset
-use crate::renderer::{Renderer, Texture as TextureBuffer};
+use log::error;
+
+use crate::renderer::{wgpu, Renderer, Texture as TextureBuffer};
 
 /// Texture asset
 pub struct Texture {
@@ -11,6 +13,13 @@ pub struct Texture {
     pub depth: u32,
     /// Raw texture data
     pub data: Vec<u8>,
+    /// Format of the texture data
+    pub format: wgpu::TextureFormat,
+    /// Pre-built mip levels, following the base level stored in `data`
+    ///
+    /// Importers generate the levels of images, that have none. Textures created in code can
+    /// get them from [`Texture::generate_mipmaps`].
+    pub levels: Vec<Vec<u8>>,
     /// Texture buffer
     pub buffer: TextureBuffer,
     /// Was the asset changed
@@ -24,6 +33,8 @@ impl Default for Texture {
             height: 0,
             depth: 0,
             data: vec![],
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            levels: vec![],
             buffer: TextureBuffer::new("Texture"),
             changed: false,
         }
@@ -31,14 +42,81 @@ impl Default for Texture {
 }
 
 impl Texture {
-    /// Loads the [`Texture`] data to a buffer
-    pub fn load(&mut self, renderer: &Renderer) {
+    /// Loads the [`Texture`] data to a buffer and returns true if the buffer is loaded
+    ///
+    /// Textures of formats, that are not supported by the device (see
+    /// [`Renderer::supports_texture_format`]), are not loaded.
+    pub fn load(&mut self, renderer: &Renderer) -> bool {
         if !self.changed && self.buffer.loaded() {
-            return;
+            return true;
+        }
+
+        if !renderer.supports_texture_format(self.format) {
+            // report the texture once, until it gets changed
+            if self.changed || self.buffer.format != self.format {
+                error!(
+                    "Texture format {:?} is not supported by the device",
+                    self.format
+                );
+            }
+            self.buffer.unload();
+            self.buffer.format = self.format;
+            self.changed = false;
+            return false;
         }
 
-        renderer.load_texture(&mut self.buffer, self.width, self.height, &[&self.data]);
+        let levels = std::iter::once(&self.data)
+            .chain(self.levels.iter())
+            .map(|level| level.as_slice())
+            .collect::<Vec<_>>();
+        // changed texture of the same layout is rewritten in place and stays bound to pipelines
+        let updated = self.buffer.format == self.format
+            && renderer.update_texture_levels(&mut self.buffer, self.width, self.height, &levels);
+        if !updated {
+            self.buffer.format = self.format;
+            renderer.load_texture_levels(&mut self.buffer, self.width, self.height, &levels);
+        }
         self.changed = false;
+        true
+    }
+
+    /// Generates mip levels from the base level using a box filter
+    ///
+    /// Levels are generated for uncompressed RGBA formats with 8 bits or 16 bits float
+    /// channels. Mip levels of other formats are left untouched. Generation is slow for big
+    /// textures, so it should not run on the render thread.
+    pub fn generate_mipmaps(&mut self) {
+        let mut pixels = match decode(self.format, &self.data) {
+            Some(pixels) => pixels,
+            None => return,
+        };
+        let (mut width, mut height) = (self.width as usize, self.height as usize);
+
+        self.levels.clear();
+        while width > 1 || height > 1 {
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+            let mut next = Vec::with_capacity(next_width * next_height);
+            for y in 0..next_height {
+                for x in 0..next_width {
+                    // odd sizes are handled by clamping to the edge
+                    let x0 = (x * 2).min(width - 1);
+                    let x1 = (x * 2 + 1).min(width - 1);
+                    let y0 = (y * 2).min(height - 1);
+                    let y1 = (y * 2 + 1).min(height - 1);
+                    let mut pixel = [0.0; 4];
+                    for (xs, ys) in [(x0, y0), (x1, y0), (x0, y1), (x1, y1)] {
+                        for (channel, value) in pixel.iter_mut().zip(pixels[ys * width + xs]) {
+                            *channel += value / 4.0;
+                        }
+                    }
+                    next.push(pixel);
+                }
+            }
+            self.levels.push(encode(self.format, &next));
+            pixels = next;
+            width = next_width;
+            height = next_height;
+        }
     }
 
     /// Unloads the [`Texture`] data from the buffer
@@ -59,3 +137,127 @@ impl Texture {
         renderer.fetch_texture(&self.buffer, [self.width, self.height, self.depth])
     }
 }
+
+/// Decodes pixels into linear RGBA values
+fn decode(format: wgpu::TextureFormat, data: &[u8]) -> Option<Vec<[f32; 4]>> {
+    let srgb = match format {
+        wgpu::TextureFormat::Rgba8Unorm => false,
+        wgpu::TextureFormat::Rgba8UnormSrgb => true,
+        wgpu::TextureFormat::Rgba16Float => {
+            return Some(
+                data.chunks_exact(8)
+                    .map(|pixel| {
+                        let mut values = [0.0; 4];
+                        for (value, bytes) in values.iter_mut().zip(pixel.chunks_exact(2)) {
+                            *value = half::f16::from_le_bytes([bytes[0], bytes[1]]).to_f32();
+                        }
+                        values
+                    })
+                    .collect(),
+            );
+        }
+        _ => return None,
+    };
+
+    Some(
+        data.chunks_exact(4)
+            .map(|pixel| {
+                let mut values = [0.0; 4];
+                for (i, value) in values.iter_mut().enumerate() {
+                    *value = pixel[i] as f32 / 255.0;
+                    // alpha is always linear
+                    if srgb && i < 3 {
+                        *value = srgb_to_linear(*value);
+                    }
+                }
+                values
+            })
+            .collect(),
+    )
+}
+
+/// Encodes linear RGBA values into pixels of the format, supported by [`decode`]
+fn encode(format: wgpu::TextureFormat, pixels: &[[f32; 4]]) -> Vec<u8> {
+    if format == wgpu::TextureFormat::Rgba16Float {
+        return pixels
+            .iter()
+            .flatten()
+            .flat_map(|value| half::f16::from_f32(*value).to_le_bytes())
+            .collect();
+    }
+
+    let srgb = format == wgpu::TextureFormat::Rgba8UnormSrgb;
+    pixels
+        .iter()
+        .flat_map(|pixel| {
+            let mut bytes = [0; 4];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let value = if srgb && i < 3 {
+                    linear_to_srgb(pixel[i])
+                } else {
+                    pixel[i]
+                };
+                *byte = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+            bytes
+        })
+        .collect()
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mipmaps() {
+        // 3x2 texture: the odd column is clamped to the edge
+        let mut texture = Texture {
+            width: 3,
+            height: 2,
+            depth: 1,
+            data: vec![
+                0, 0, 0, 255, 255, 255, 255, 255, 100, 0, 0, 255, //
+                0, 0, 0, 0, 255, 255, 255, 0, 100, 0, 0, 0,
+            ],
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            ..Default::default()
+        };
+        texture.generate_mipmaps();
+        assert_eq!(texture.levels, vec![vec![128, 128, 128, 128]]);
+
+        // sRGB values are averaged in the linear space
+        texture.format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        texture.generate_mipmaps();
+        assert_eq!(texture.levels, vec![vec![188, 188, 188, 128]]);
+
+        texture.width = 8;
+        texture.height = 4;
+        texture.data = vec![255; 8 * 4 * 4];
+        texture.generate_mipmaps();
+        let sizes = texture.levels.iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![4 * 2 * 4, 2 * 4, 4]);
+        assert!(texture.levels.iter().flatten().all(|value| *value == 255));
+
+        // compressed textures keep their levels
+        texture.format = wgpu::TextureFormat::Bc1RgbaUnorm;
+        texture.levels = vec![vec![1; 8]];
+        texture.generate_mipmaps();
+        assert_eq!(texture.levels, vec![vec![1; 8]]);
+    }
+}
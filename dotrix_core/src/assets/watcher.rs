@@ -0,0 +1,195 @@
+//! Watcher of imported files
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use log::error;
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode};
+
+use super::resource::Resource;
+use crate::id::Id;
+
+/// Time to wait for more changes of a file before reporting it
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Files watcher
+///
+/// Directories of files are being watched instead of files themselves, so changes made by
+/// editors that replace files instead of writing them are also detected. The system watcher is
+/// started with the first watched file, only if the [`Watcher`] is enabled.
+pub struct Watcher {
+    enabled: bool,
+    backend: Option<Backend>,
+    directories: HashSet<PathBuf>,
+    files: HashMap<PathBuf, Id<Resource>>,
+    changes: HashMap<Id<Resource>, Instant>,
+}
+
+/// System watcher with the receiver of its events
+struct Backend {
+    watcher: RecommendedWatcher,
+    receiver: mpsc::Receiver<notify::Result<Event>>,
+}
+
+impl Watcher {
+    /// Constructs new [`Watcher`]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            backend: None,
+            directories: HashSet::new(),
+            files: HashMap::new(),
+            changes: HashMap::new(),
+        }
+    }
+
+    /// Enables or disables watching of files
+    ///
+    /// Disabled watcher stops the system watcher, but keeps the list of files to watch them
+    /// again once enabled.
+    pub fn enable(&mut self, enable: bool) {
+        self.enabled = enable;
+        if !enable {
+            self.backend = None;
+            self.directories.clear();
+            self.changes.clear();
+            return;
+        }
+        let directories = self
+            .files
+            .keys()
+            .filter_map(|path| path.parent().map(PathBuf::from))
+            .collect::<HashSet<_>>();
+        for directory in directories {
+            self.watch_directory(directory);
+        }
+    }
+
+    /// Starts watching of the [`Resource`] file
+    pub fn watch(&mut self, path: &Path, resource: Id<Resource>) {
+        let (directory, file_name) = match (
+            path.parent().and_then(|p| p.canonicalize().ok()),
+            path.file_name(),
+        ) {
+            (Some(directory), Some(file_name)) => (directory, file_name),
+            _ => return,
+        };
+
+        self.files.insert(directory.join(file_name), resource);
+        if self.enabled {
+            self.watch_directory(directory);
+        }
+    }
+
+    fn watch_directory(&mut self, directory: PathBuf) {
+        use notify::Watcher;
+
+        if self.directories.contains(&directory) {
+            return;
+        }
+        if self.backend.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            let watcher = notify::recommended_watcher(move |event| {
+                // receiver is dropped together with the watcher
+                sender.send(event).ok();
+            });
+            match watcher {
+                Ok(watcher) => self.backend = Some(Backend { watcher, receiver }),
+                Err(e) => {
+                    error!("Files watcher is not available: {:?}", e);
+                    // do not try again for each file
+                    self.enabled = false;
+                    return;
+                }
+            }
+        }
+        let backend = self.backend.as_mut().unwrap();
+        if let Err(e) = backend
+            .watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+        {
+            error!("Can't watch `{:?}`: {:?}", directory, e);
+            return;
+        }
+        self.directories.insert(directory);
+    }
+
+    /// Returns resources which files were changed
+    pub fn changes(&mut self) -> Vec<Id<Resource>> {
+        let now = Instant::now();
+
+        let backend = match self.backend.as_ref() {
+            Some(backend) => backend,
+            None => return Vec::new(),
+        };
+
+        while let Ok(event) = backend.receiver.try_recv() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    error!("Files watcher error: {:?}", e);
+                    continue;
+                }
+            };
+
+            match event.kind {
+                EventKind::Create(_) => (),
+                EventKind::Modify(kind) if !matches!(kind, ModifyKind::Metadata(_)) => (),
+                _ => continue,
+            };
+
+            for path in event.paths.iter() {
+                if let Some(resource) = self.files.get(path) {
+                    self.changes.insert(*resource, now);
+                }
+            }
+        }
+
+        let changed = self
+            .changes
+            .iter()
+            .filter(|(_, time)| now.duration_since(**time) >= DEBOUNCE)
+            .map(|(resource, _)| *resource)
+            .collect::<Vec<_>>();
+
+        for resource in changed.iter() {
+            self.changes.remove(resource);
+        }
+
+        changed
+    }
+
+    /// Reports the resource as changed again on the next call of [`Watcher::changes`]
+    pub fn postpone(&mut self, resource: Id<Resource>) {
+        self.changes.insert(resource, Instant::now() - DEBOUNCE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn lazy_backend() {
+        let dir = TempDir::new("watcher");
+        let path = dir.join("shader.wgsl");
+        std::fs::write(&path, "// shader").unwrap();
+
+        let mut watcher = Watcher::new(false);
+        watcher.watch(&path, Id::new(1));
+        assert!(watcher.backend.is_none());
+        assert!(watcher.changes().is_empty());
+
+        watcher.enable(true);
+        assert!(watcher.backend.is_some());
+        assert_eq!(watcher.directories.len(), 1);
+
+        watcher.enable(false);
+        assert!(watcher.backend.is_none());
+        assert!(watcher.directories.is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+//! Command-line packer of asset directories
+//!
+//! Usage: `dotrix-pack <assets directory> <output file>`
+use std::{fs::File, io::BufWriter, path::Path, process};
+
+use dotrix_core::assets::PackWriter;
+
+fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() != 3 {
+        eprintln!("Usage: {} <assets directory> <output file>", args[0]);
+        process::exit(1);
+    }
+
+    let mut pack = PackWriter::new();
+    if let Err(e) = pack.add_directory(Path::new(&args[1])) {
+        eprintln!("Can't read `{}`: {}", args[1], e);
+        process::exit(1);
+    }
+
+    let result = File::create(&args[2]).and_then(|file| pack.write(&mut BufWriter::new(file)));
+    if let Err(e) = result {
+        eprintln!("Can't write `{}`: {}", args[2], e);
+        process::exit(1);
+    }
+
+    println!("{} files packed into `{}`", pack.len(), args[2]);
+}
@@ -140,17 +140,13 @@ pub fn startup(mut globals: Mut<Globals>) {
 }
 
 /// Loads the ProjView binding with current matrix value
-pub fn load(
-    mut globals: Mut<Globals>,
-    mut camera: Mut<Camera>,
-    renderer: Const<Renderer>,
-    window: Const<Window>,
-) {
+pub fn load(mut globals: Mut<Globals>, mut camera: Mut<Camera>, renderer: Const<Renderer>) {
     // Calculate projection matrix
     if camera.proj.is_none() {
+        let surface_size = renderer.surface_size();
         camera.proj = Some(perspective(
             Rad(camera.fov),
-            window.aspect_ratio(),
+            surface_size.x / surface_size.y,
             camera.near_plane,
             camera.far_plane,
         ));
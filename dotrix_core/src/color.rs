@@ -2,8 +2,10 @@
 use std::convert::From;
 use std::ops::{Index, IndexMut};
 
+use serde::{Deserialize, Serialize};
+
 /// RGBA Color.
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Color {
     /// Red channel. Should be in range from 0 to 1.
     pub r: f32,
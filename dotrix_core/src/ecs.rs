@@ -2,8 +2,10 @@
 
 use crate::application::{IntoService, Services};
 use crate::state::Rule;
-use crate::{Id, State};
+pub use crate::world::{Added, Changed, Filter, Tracked, With, Without, Write};
+use crate::{Id, State, Window, World};
 use core::ops::{Deref, DerefMut};
+use std::any::TypeId;
 use std::hash::Hash;
 
 /// Entity structure has only id field and represent an agregation of components
@@ -16,6 +18,12 @@ impl From<u64> for Entity {
     }
 }
 
+impl From<Entity> for u64 {
+    fn from(entity: Entity) -> Self {
+        entity.0
+    }
+}
+
 /// Any data structure can be a component
 pub trait Component: Send + Sync + 'static {}
 impl<T: Send + Sync + 'static> Component for T {}
@@ -59,6 +67,9 @@ pub enum RunLevel {
     Startup,
     /// Execution on the beginning of each frame
     Bind,
+    /// Execution with a fixed rate for physics, networking and other simulations, that should
+    /// not depend on FPS (see [`crate::Frame::set_tick_rate`])
+    FixedUpdate,
     /// Execution on every frame for most of the calculations and updates (Default)
     Update,
     /// Execution on every frame to load data to GPU buffers right before rendering
@@ -81,6 +92,8 @@ impl From<&str> for RunLevel {
             RunLevel::Startup
         } else if name.ends_with("::bind") {
             RunLevel::Bind
+        } else if name.ends_with("::fixed_update") {
+            RunLevel::FixedUpdate
         } else if name.ends_with("::load") {
             RunLevel::Load
         } else if name.ends_with("::compute") {
@@ -151,15 +164,161 @@ impl SystemOption<Priority> for System {
     }
 }
 
+/// Option to run the [`System`] exclusively
+///
+/// Systems are executed in parallel, if they don't access the same services mutably. Exclusive
+/// system never runs in parallel with others, use it for systems having side effects outside of
+/// services (e.g. thread dependent calls). Systems accessing the [`Window`] service are always
+/// exclusive and run on the main thread.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Exclusive;
+
+/// [`Exclusive`] option implementation
+impl SystemOption<Exclusive> for System {
+    fn set_option(&mut self, _option: Exclusive) {
+        self.data.set_exclusive();
+    }
+}
+
+/// Option to label the [`System`] for [`Before`] and [`After`] options of other systems
+///
+/// Labels must be unique among systems of the same [`RunLevel`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Label(pub &'static str);
+
+/// Option to run the [`System`] after the other one
+///
+/// The label is either a [`Label`] of the other system or the full path of its function, e.g.
+/// `dotrix_pbr::light::load`. Systems must be of the same [`RunLevel`], otherwise the option
+/// has no effect and a warning is logged on startup.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct After(pub &'static str);
+
+/// Option to run the [`System`] before the other one
+///
+/// The label is either a [`Label`] of the other system or the full path of its function, e.g.
+/// `dotrix_pbr::light::load`. Systems must be of the same [`RunLevel`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Before(pub &'static str);
+
+/// [`Label`] option implementation
+impl SystemOption<Label> for System {
+    fn set_option(&mut self, option: Label) {
+        self.data.set_label(option.0);
+    }
+}
+
+/// [`After`] option implementation
+impl SystemOption<After> for System {
+    fn set_option(&mut self, option: After) {
+        self.data.push_after(option.0);
+    }
+}
+
+/// [`Before`] option implementation
+impl SystemOption<Before> for System {
+    fn set_option(&mut self, option: Before) {
+        self.data.push_before(option.0);
+    }
+}
+
+/// Error of systems ordering
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OrderError {
+    /// Systems depend on each other in a loop
+    Cycle(Vec<&'static str>),
+    /// Label points to several systems
+    Ambiguous(&'static str, Vec<&'static str>),
+}
+
+impl std::error::Error for OrderError {}
+
+impl std::fmt::Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderError::Cycle(systems) => {
+                write!(f, "Systems ordering has a cycle: {}", systems.join(" -> "))
+            }
+            OrderError::Ambiguous(label, systems) => write!(
+                f,
+                "Label `{}` points to several systems: {}",
+                label,
+                systems.join(", ")
+            ),
+        }
+    }
+}
+
+/// Access of a [`System`] to a service
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Access {
+    /// Immutable access to the service
+    Read(TypeId),
+    /// Mutable access to the service
+    Write(TypeId),
+}
+
+impl Access {
+    /// Returns type id of the accessed service
+    pub fn service(&self) -> TypeId {
+        match self {
+            Access::Read(key) | Access::Write(key) => *key,
+        }
+    }
+
+    /// Returns true if two accesses can't happen at the same time
+    pub fn conflicts(&self, other: &Access) -> bool {
+        match (self, other) {
+            (Access::Read(_), Access::Read(_)) => false,
+            (Access::Read(a), Access::Write(b))
+            | (Access::Write(a), Access::Read(b))
+            | (Access::Write(a), Access::Write(b)) => a == b,
+        }
+    }
+}
+
 struct SystemData<Run, Ctx>
 where
-    Run: FnMut(&mut Ctx, &mut Services) + Send + Sync,
+    Run: FnMut(&mut Ctx, &Services) + Send + Sync,
 {
     name: &'static str,
     run: Run,
     ctx: Ctx,
     priority: Priority,
     rules: Vec<Rule>,
+    access: Vec<Access>,
+    exclusive: bool,
+    label: Option<&'static str>,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    /// Tick of the [`World`] at the last run of the system
+    last_run: u64,
+}
+
+impl<Run, Ctx> SystemData<Run, Ctx>
+where
+    Run: FnMut(&mut Ctx, &Services) + Send + Sync,
+{
+    /// Executes the system, letting its queries filter changes made since its last run
+    fn execute(&mut self, app: &Services) {
+        let world = self
+            .access
+            .iter()
+            .any(|access| access.service() == TypeId::of::<World>())
+            .then(|| app.get_ptr::<World>())
+            .flatten();
+
+        if let Some(world) = world {
+            // SAFETY: systems accessing the world never run in parallel and do not hold it
+            // between runs
+            self.last_run = unsafe { (*world).enter_system(self.last_run) };
+        }
+        (self.run)(&mut self.ctx, app);
+        if let Some(world) = world {
+            // SAFETY: see above
+            unsafe { (*world).exit_system() };
+        }
+    }
 }
 
 /// Abstraction for [`System`] prepared to be integrated into engine
@@ -167,7 +326,7 @@ pub trait Systemized: Send + Sync {
     /// Returns name of the system
     fn name(&self) -> &'static str;
     /// Executes system cylce
-    fn run(&mut self, app: &mut Services, state: Id<State>);
+    fn run(&mut self, app: &Services, state: Id<State>);
     /// Returns priority of the system
     fn priority(&self) -> Priority;
     /// Sets priority for the system
@@ -176,20 +335,60 @@ pub trait Systemized: Send + Sync {
     fn push_rule(&mut self, rule: Rule);
     /// Returns true is system can run at state
     fn run_at_state(&self, state: Id<State>) -> bool;
+    /// Returns system execution rules
+    fn rules(&self) -> &[Rule];
+    /// Executes system cycle if it has a hook rule for the state transition
+    fn run_hook(&mut self, app: &Services, transition: Rule);
+    /// Returns list of services accessed by the system
+    fn access(&self) -> &[Access];
+    /// Returns true if the system must not run in parallel with others
+    fn exclusive(&self) -> bool;
+    /// Forbids parallel execution of the system
+    fn set_exclusive(&mut self);
+    /// Returns label of the system
+    fn label(&self) -> Option<&'static str>;
+    /// Sets label of the system
+    fn set_label(&mut self, label: &'static str);
+    /// Returns labels of systems, that must run after the system
+    fn before(&self) -> &[&'static str];
+    /// Returns labels of systems, that must run before the system
+    fn after(&self) -> &[&'static str];
+    /// Adds label of a system, that must run after the system
+    fn push_before(&mut self, label: &'static str);
+    /// Adds label of a system, that must run before the system
+    fn push_after(&mut self, label: &'static str);
+    /// Returns true if the label points to the system
+    fn is_labeled(&self, label: &str) -> bool {
+        self.name() == label || self.label() == Some(label)
+    }
+    /// Returns true if the system must run before the other one
+    fn precedes(&self, other: &dyn Systemized) -> bool {
+        self.before().iter().any(|label| other.is_labeled(label))
+            || other.after().iter().any(|label| self.is_labeled(label))
+    }
+    /// Returns true if the system can't run in parallel with the other one
+    fn conflicts(&self, other: &dyn Systemized) -> bool {
+        self.exclusive()
+            || other.exclusive()
+            || self
+                .access()
+                .iter()
+                .any(|a| other.access().iter().any(|b| a.conflicts(b)))
+    }
 }
 
 impl<Run, Ctx> Systemized for SystemData<Run, Ctx>
 where
-    Run: FnMut(&mut Ctx, &mut Services) + Send + Sync,
+    Run: FnMut(&mut Ctx, &Services) + Send + Sync,
     Ctx: SystemContext,
 {
     fn name(&self) -> &'static str {
         self.name
     }
 
-    fn run(&mut self, app: &mut Services, state: Id<State>) {
+    fn run(&mut self, app: &Services, state: Id<State>) {
         if self.run_at_state(state) {
-            (self.run)(&mut self.ctx, app);
+            self.execute(app);
         }
     }
 
@@ -223,12 +422,59 @@ where
                             no_match_result = true;
                         }
                     }
+                    Rule::OnEnter(_) | Rule::OnExit(_) | Rule::OnPause(_) | Rule::OnResume(_) => {}
                 };
             }
             return no_match_result;
         }
         true
     }
+
+    fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    fn run_hook(&mut self, app: &Services, transition: Rule) {
+        if self.rules.contains(&transition) {
+            self.execute(app);
+        }
+    }
+
+    fn access(&self) -> &[Access] {
+        &self.access
+    }
+
+    fn exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    fn set_exclusive(&mut self) {
+        self.exclusive = true;
+    }
+
+    fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    fn set_label(&mut self, label: &'static str) {
+        self.label = Some(label);
+    }
+
+    fn before(&self) -> &[&'static str] {
+        &self.before
+    }
+
+    fn after(&self) -> &[&'static str] {
+        &self.after
+    }
+
+    fn push_before(&mut self, label: &'static str) {
+        self.before.push(label);
+    }
+
+    fn push_after(&mut self, label: &'static str) {
+        self.after.push(label);
+    }
 }
 
 /// Abstraction for a function that can be turned into a [`System`]
@@ -250,6 +496,9 @@ macro_rules! impl_into_system {
             #[allow(unused)]
             fn into_system(mut self: Fun) -> Box<dyn Systemized>
             {
+                let access = vec![$($i::access(),)*];
+                // window can be bound to the main thread on some platforms
+                let exclusive = access.iter().any(|a| a.service() == TypeId::of::<Window>());
                 let data: SystemData<_, ($($context)*)> = SystemData {
                     name: std::any::type_name::<Fun>(),
                     run: move |ctx, app| {
@@ -266,6 +515,12 @@ macro_rules! impl_into_system {
                     priority: Priority::Normal,
                     ctx: ($($context::default())*),
                     rules: Vec::new(),
+                    access,
+                    exclusive,
+                    label: None,
+                    before: Vec::new(),
+                    after: Vec::new(),
+                    last_run: 0,
                 };
                 Box::new(data)
             }
@@ -381,7 +636,9 @@ pub trait Accessor: Send + Sync {
     /// Type of Service to be accessed
     type Item: IntoService;
     /// Fetches the Service from the storage
-    fn fetch(services: &mut Services) -> Self;
+    fn fetch(services: &Services) -> Self;
+    /// Returns access to the Service
+    fn access() -> Access;
 }
 
 impl<T> Accessor for Mut<T>
@@ -389,13 +646,14 @@ where
     T: IntoService,
 {
     type Item = T;
-    fn fetch(services: &mut Services) -> Self {
-        let service: &mut T = services
-            .get_mut::<T>()
+    fn fetch(services: &Services) -> Self {
+        let value = services
+            .get_ptr::<T>()
             .unwrap_or_else(|| panic!("Service {} does not exist", std::any::type_name::<T>()));
-        Mut {
-            value: service as *mut T,
-        }
+        Mut { value }
+    }
+    fn access() -> Access {
+        Access::Write(TypeId::of::<T>())
     }
 }
 
@@ -404,7 +662,7 @@ where
     T: IntoService,
 {
     type Item = T;
-    fn fetch(service: &mut Services) -> Self {
+    fn fetch(service: &Services) -> Self {
         let service: &T = service
             .get::<T>()
             .unwrap_or_else(|| panic!("Service {} does not exist", std::any::type_name::<T>()));
@@ -412,12 +670,52 @@ where
             value: service as *const T,
         }
     }
+    fn access() -> Access {
+        let key = TypeId::of::<T>();
+        // World queries can mutate components through a shared reference
+        if key == TypeId::of::<World>() {
+            Access::Write(key)
+        } else {
+            Access::Read(key)
+        }
+    }
+}
+
+/// Optional mutable accessor, that is `None` if the Service does not exist
+impl<T> Accessor for Option<Mut<T>>
+where
+    T: IntoService,
+{
+    type Item = T;
+    fn fetch(services: &Services) -> Self {
+        services.get_ptr::<T>().map(|value| Mut { value })
+    }
+    fn access() -> Access {
+        Mut::<T>::access()
+    }
+}
+
+/// Optional imutable accessor, that is `None` if the Service does not exist
+impl<T> Accessor for Option<Const<T>>
+where
+    T: IntoService,
+{
+    type Item = T;
+    fn fetch(services: &Services) -> Self {
+        services.get::<T>().map(|service| Const {
+            value: service as *const T,
+        })
+    }
+    fn access() -> Access {
+        Const::<T>::access()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::application::Services;
     use crate::ecs::{Const, Context, Mut, RunLevel, System};
+    use crate::world::{Changed, Write};
     use crate::{State, World};
 
     struct MyComponent(u64);
@@ -431,10 +729,61 @@ mod tests {
         let mut services = Services::new();
         services.add(World::new());
         let mut s = System::from(my_system);
-        s.data.run(&mut services, State::meta());
+        s.data.run(&services, State::meta());
         assert_eq!(services.get::<World>().unwrap().counter(), 1);
     }
 
+    struct Changes(usize);
+
+    fn read_changes(world: Const<World>, mut changes: Mut<Changes>) {
+        changes.0 = world
+            .query_filtered::<(&MyComponent,), (Changed<MyComponent>,)>()
+            .count();
+    }
+
+    fn write_changes(world: Const<World>) {
+        for (component,) in world.query::<(&mut MyComponent,)>() {
+            component.0 += 1;
+        }
+    }
+
+    fn skip_changes(world: Const<World>) {
+        for (component,) in world.query::<(Write<MyComponent>,)>() {
+            assert!(component.0 > 0);
+        }
+    }
+
+    #[test]
+    fn changes_since_last_run() {
+        let mut services = Services::new();
+        services.add(World::new());
+        services.add(Changes(0));
+        services
+            .get_mut::<World>()
+            .unwrap()
+            .spawn(Some((MyComponent(1),)));
+
+        let mut reader = System::from(read_changes);
+        let mut writer = System::from(write_changes);
+        let mut skipper = System::from(skip_changes);
+        let changes = |system: &mut System, services: &Services| {
+            system.data.run(services, State::meta());
+            services.get::<Changes>().unwrap().0
+        };
+
+        assert_eq!(changes(&mut reader, &services), 1);
+        assert_eq!(changes(&mut reader, &services), 0);
+        // changes made after the reader are seen on its next run
+        assert_eq!(changes(&mut writer, &services), 0);
+        assert_eq!(changes(&mut reader, &services), 1);
+        changes(&mut writer, &services);
+        services.get_mut::<World>().unwrap().advance();
+        assert_eq!(changes(&mut reader, &services), 1);
+        // access through `Write` without writing is not a change
+        changes(&mut skipper, &services);
+        assert_eq!(changes(&mut reader, &services), 0);
+    }
+
     #[derive(Default)]
     struct MyContext(u64);
 
@@ -446,12 +795,30 @@ mod tests {
         service.data = ctx.0;
     }
 
+    fn optional_services(world: Option<Const<World>>, changes: Option<Mut<Changes>>) {
+        if let (Some(world), Some(mut changes)) = (world, changes) {
+            changes.0 = world.counter() as usize;
+        }
+    }
+
+    #[test]
+    fn optional_system() {
+        let mut services = Services::new();
+        let mut s = System::from(optional_services);
+        s.data.run(&services, State::meta());
+
+        services.add(World::new());
+        services.add(Changes(1));
+        s.data.run(&services, State::meta());
+        assert_eq!(services.get::<Changes>().unwrap().0, 0);
+    }
+
     #[test]
     fn custom_system() {
         let mut services = Services::new();
         services.add(MyService { data: 123 });
         let mut s = System::from(my_system_with_context);
-        s.data.run(&mut services, State::meta());
+        s.data.run(&services, State::meta());
         assert_eq!(services.get::<MyService>().unwrap().data, 0);
     }
 
@@ -462,6 +829,7 @@ mod tests {
     fn render(_service: Const<MyService>) {}
     fn release(_service: Const<MyService>) {}
     fn resize(_service: Const<MyService>) {}
+    fn fixed_update(_service: Const<MyService>) {}
 
     #[test]
     fn system_runlevel_autodetect() {
@@ -486,6 +854,9 @@ mod tests {
         let system = System::from(resize);
         assert_eq!(system.run_level, RunLevel::Resize);
 
+        let system = System::from(fixed_update);
+        assert_eq!(system.run_level, RunLevel::FixedUpdate);
+
         let system = System::from(my_system_with_context);
         assert_eq!(system.run_level, RunLevel::Update);
     }
@@ -1,53 +1,158 @@
 //! Frame service implementations and systems
 use crate::ecs::Mut;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// Source of time for the [`Frame`] service
+pub trait Clock: Send + Sync + 'static {
+    /// Returns [`Duration`] elapsed since the clock origin
+    fn now(&self) -> Duration;
+}
+
+/// Wall clock based on [`Instant`] (default)
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    /// Constructs the clock with origin at current moment
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Clock that moves only when it is told to
+///
+/// The clock can be cloned, all clones share the same time, so one instance can be passed to the
+/// [`Frame`] and another one can be kept to control it from outside.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    time: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// Constructs the clock with zero time
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by `delta`
+    pub fn advance(&self, delta: Duration) {
+        *self.time.lock().unwrap() += delta;
+    }
+
+    /// Sets the clock time
+    pub fn set(&self, time: Duration) {
+        *self.time.lock().unwrap() = time;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.time.lock().unwrap()
+    }
+}
+
+/// Default rate of fixed updates per second
+const TICK_RATE: u32 = 60;
+
+/// Default maximal number of fixed updates per frame
+const MAX_FIXED_STEPS: u32 = 5;
+
 /// Frame tracking service
 ///
-/// Calculates FPS and tracks delta time between renderings
+/// Calculates FPS and tracks delta time between renderings. It also accumulates the time for
+/// the [`crate::RunLevel::FixedUpdate`] systems, that run with a constant rate independently of
+/// the FPS.
 pub struct Frame {
-    first: Option<Instant>,
-    current: Option<Instant>,
-    counter_start: Instant,
+    clock: Box<dyn Clock>,
+    current: Option<Duration>,
+    counter_start: Duration,
     counter: u32,
     fps: Option<f32>,
     delta: Duration,
     time: Duration,
+    step: Option<Duration>,
+    fixed_delta: Duration,
+    max_fixed_steps: u32,
+    fixed_steps: u32,
+    accumulator: Duration,
 }
 
 impl Frame {
     /// Constructs service instance
     pub fn new() -> Self {
+        Self::with_clock(SystemClock::new())
+    }
+
+    /// Constructs service instance using custom [`Clock`]
+    pub fn with_clock<T: Clock>(clock: T) -> Self {
         Self {
-            first: None,
+            clock: Box::new(clock),
             current: None,
-            counter_start: Instant::now(),
+            counter_start: Duration::from_secs(0),
             counter: 0,
             fps: None,
             delta: Duration::from_secs(0),
             time: Duration::from_secs(0),
+            step: None,
+            fixed_delta: Duration::from_secs(1) / TICK_RATE,
+            max_fixed_steps: MAX_FIXED_STEPS,
+            fixed_steps: 0,
+            accumulator: Duration::from_secs(0),
         }
     }
 
-    pub(crate) fn next(&mut self) {
-        let now = Instant::now();
-        if let Some(first) = self.first {
-            self.time = now - first;
-        } else {
-            self.first = Some(now);
-            self.counter_start = now;
-        }
+    /// Replaces the [`Clock`] of the service
+    pub fn set_clock<T: Clock>(&mut self, clock: T) {
+        self.clock = Box::new(clock);
+        self.current = None;
+    }
 
-        if let Some(current) = self.current {
-            self.delta = now - current;
-        }
+    /// Forces the next frame to last exactly `delta` regardless of the [`Clock`]
+    pub fn step(&mut self, delta: Duration) {
+        self.step = Some(delta);
+    }
+
+    pub(crate) fn next(&mut self) {
+        let now = self.clock.now();
+        let delta = match self.step.take() {
+            Some(step) => step,
+            None => self
+                .current
+                .map(|current| now.saturating_sub(current))
+                .unwrap_or_else(|| Duration::from_secs(0)),
+        };
         self.current = Some(now);
+        self.delta = delta;
+        self.time += delta;
+
+        self.accumulator += delta;
+        let fixed_delta = self.fixed_delta.as_nanos();
+        let steps = self.accumulator.as_nanos() / fixed_delta;
+        self.fixed_steps = steps.min(self.max_fixed_steps as u128) as u32;
+        // time that can't be caught up is dropped
+        self.accumulator = Duration::from_nanos((self.accumulator.as_nanos() % fixed_delta) as u64);
 
-        let duration = now - self.counter_start;
+        let duration = self.time - self.counter_start;
         if duration > Duration::from_secs(1) {
             self.fps = Some(self.counter as f32 / duration.as_secs_f32());
             self.counter = 0;
-            self.counter_start = now;
+            self.counter_start = self.time;
         }
 
         self.counter += 1;
@@ -59,9 +164,14 @@ impl Frame {
     }
 
     /// Returns FPS
+    ///
+    /// Zero is returned, until some time passes since the start.
     pub fn fps(&self) -> f32 {
         self.fps.unwrap_or_else(|| {
-            let duration = Instant::now() - self.counter_start;
+            let duration = self.time - self.counter_start;
+            if duration.is_zero() {
+                return 0.0;
+            }
             self.counter as f32 / duration.as_secs_f32()
         })
     }
@@ -70,6 +180,48 @@ impl Frame {
     pub fn delta(&self) -> Duration {
         self.delta
     }
+
+    /// Sets number of fixed updates per second
+    pub fn set_tick_rate(&mut self, rate: u32) {
+        assert!(rate > 0, "Tick rate must be positive");
+        self.fixed_delta = Duration::from_secs(1) / rate;
+    }
+
+    /// Returns number of fixed updates per second
+    pub fn tick_rate(&self) -> f32 {
+        1.0 / self.fixed_delta.as_secs_f32()
+    }
+
+    /// Returns [`Duration`] of a fixed update
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    /// Sets maximal number of fixed updates per frame
+    ///
+    /// If a frame takes too long, the rest of the time is dropped, so the simulation slows down
+    /// instead of spending even more time on catching up.
+    pub fn set_max_fixed_steps(&mut self, steps: u32) {
+        self.max_fixed_steps = steps;
+    }
+
+    /// Returns maximal number of fixed updates per frame
+    pub fn max_fixed_steps(&self) -> u32 {
+        self.max_fixed_steps
+    }
+
+    /// Returns number of fixed updates to run in current frame
+    pub fn fixed_steps(&self) -> u32 {
+        self.fixed_steps
+    }
+
+    /// Returns interpolation factor between the last and the next fixed updates
+    ///
+    /// The value is in range from 0.0 to 1.0 and can be used to blend states of the two last
+    /// fixed updates for smooth rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.fixed_delta.as_secs_f32()
+    }
 }
 
 impl Default for Frame {
@@ -83,3 +235,82 @@ impl Default for Frame {
 pub fn bind(mut frame: Mut<Frame>) {
     frame.next();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Frame, ManualClock};
+    use std::time::Duration;
+
+    #[test]
+    fn manual_clock() {
+        let clock = ManualClock::new();
+        let mut frame = Frame::with_clock(clock.clone());
+
+        frame.next();
+        assert_eq!(frame.delta(), Duration::from_secs(0));
+        assert_eq!(frame.time(), Duration::from_secs(0));
+
+        clock.advance(Duration::from_millis(20));
+        frame.next();
+        assert_eq!(frame.delta(), Duration::from_millis(20));
+        assert_eq!(frame.time(), Duration::from_millis(20));
+
+        clock.advance(Duration::from_millis(30));
+        frame.next();
+        assert_eq!(frame.delta(), Duration::from_millis(30));
+        assert_eq!(frame.time(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn fps_at_start() {
+        let clock = ManualClock::new();
+        let mut frame = Frame::with_clock(clock.clone());
+        assert!(frame.fps().abs() < f32::EPSILON);
+        frame.next();
+        assert!(frame.fps().abs() < f32::EPSILON);
+
+        clock.advance(Duration::from_millis(500));
+        frame.next();
+        assert!((frame.fps() - 4.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn fixed_steps() {
+        let mut frame = Frame::with_clock(ManualClock::new());
+        for _ in 0..4 {
+            frame.step(Duration::from_millis(250));
+            frame.next();
+            assert_eq!(frame.delta(), Duration::from_millis(250));
+        }
+        assert_eq!(frame.time(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn fixed_update_accumulator() {
+        let mut frame = Frame::with_clock(ManualClock::new());
+        frame.set_tick_rate(50);
+        frame.set_max_fixed_steps(3);
+        assert_eq!(frame.fixed_delta(), Duration::from_millis(20));
+
+        frame.step(Duration::from_millis(30));
+        frame.next();
+        assert_eq!(frame.fixed_steps(), 1);
+        assert!((frame.alpha() - 0.5).abs() < f32::EPSILON);
+
+        frame.step(Duration::from_millis(15));
+        frame.next();
+        assert_eq!(frame.fixed_steps(), 1);
+        assert!((frame.alpha() - 0.25).abs() < f32::EPSILON);
+
+        frame.step(Duration::from_millis(10));
+        frame.next();
+        assert_eq!(frame.fixed_steps(), 0);
+        assert!((frame.alpha() - 0.75).abs() < f32::EPSILON);
+
+        // catch up is limited
+        frame.step(Duration::from_millis(205));
+        frame.next();
+        assert_eq!(frame.fixed_steps(), 3);
+        assert!(frame.alpha() < 1.0);
+    }
+}
@@ -11,6 +11,9 @@ mod pose;
 mod state;
 mod world;
 
+#[cfg(test)]
+mod test_utils;
+
 pub mod animation;
 pub mod assets;
 pub mod camera;
@@ -20,6 +23,8 @@ pub mod id;
 pub mod input;
 pub mod ray;
 pub mod renderer;
+pub mod scene;
+pub mod tasks;
 pub mod transform;
 pub mod window;
 
@@ -38,9 +43,10 @@ pub use pose::Pose;
 pub use ray::Ray;
 pub use renderer::Renderer;
 pub use state::State;
-pub use transform::Transform;
+pub use tasks::Tasks;
+pub use transform::{Children, GlobalTransform, Parent, Transform};
 pub use window::{Monitor, VideoMode, Window};
-pub use world::World;
+pub use world::{Commands, World};
 
 /// Count parameters
 #[macro_export]
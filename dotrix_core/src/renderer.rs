@@ -9,7 +9,7 @@ mod sampler;
 mod shader;
 mod texture;
 
-use dotrix_math::{Mat4, Vec2};
+use dotrix_math::{Mat4, Vec2, Vec2u};
 
 use crate::assets::{Mesh, Shader};
 use crate::ecs::{Const, Mut};
@@ -51,9 +51,19 @@ pub struct Renderer {
     pub context: Option<Context>,
     /// When dirty, renderer will try to load missing pipelines on frame binding
     pub dirty: bool,
+    /// Size of the offscreen render target. If set, renderer works headless without a window
+    pub offscreen: Option<Vec2u>,
 }
 
 impl Renderer {
+    /// Constructs the service rendering into an offscreen texture of the defined size
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self {
+            offscreen: Some(Vec2u::new(width, height)),
+            ..Default::default()
+        }
+    }
+
     /// Sets default clear color
     pub fn set_clear_color(&mut self, color: Color) {
         self.clear_color = color;
@@ -97,9 +107,48 @@ impl Renderer {
         texture.load(self.context(), width, height, layers);
     }
 
+    /// Loads the texture buffer with mip levels to GPU, starting from the base level
+    ///
+    /// Texture is recreated the same way as by [`Renderer::load_texture`]
+    pub fn load_texture_levels(
+        &self,
+        texture: &mut Texture,
+        width: u32,
+        height: u32,
+        levels: &[&[u8]],
+    ) {
+        texture.load_levels(self.context(), width, height, levels);
+    }
+
+    /// Rewrites all mip levels of the loaded texture buffer, starting from the base level
+    ///
+    /// Unlike [`Renderer::load_texture_levels`], the texture is not recreated and stays bound
+    /// to pipelines. Returns false without writing, if the texture is not loaded or its size or
+    /// number of levels differ.
+    pub fn update_texture_levels(
+        &self,
+        texture: &mut Texture,
+        width: u32,
+        height: u32,
+        levels: &[&[u8]],
+    ) -> bool {
+        texture.update_levels(self.context(), width, height, levels)
+    }
+
+    /// Returns true if the device supports the texture format
+    ///
+    /// Block compressed formats are supported only if the adapter has corresponding features.
+    pub fn supports_texture_format(&self, format: wgpu::TextureFormat) -> bool {
+        self.context()
+            .device
+            .features()
+            .contains(format.describe().required_features)
+    }
+
     /// Load data from cpu to a texture buffer on GPU
     /// This is a noop if texture has not been loaded with `load_texture`
     /// Unexpected results/errors occur if the dimensions differs from it dimensions at load time
+    /// Only base mip level is written, see [`Renderer::update_texture_levels`]
     pub fn update_texture<'a>(
         &self,
         texture: &mut Texture,
@@ -164,6 +213,13 @@ impl Renderer {
         texture.fetch_from_gpu(dimensions, self.context_mut())
     }
 
+    /// Reads the last rendered frame of a headless renderer as tightly packed RGBA8 rows
+    ///
+    /// Returns `None` if the renderer draws to a window
+    pub fn capture(&self) -> Option<Vec<u8>> {
+        self.context().read_offscreen()
+    }
+
     /// Forces engine to reload shaders
     pub fn reload(&mut self) {
         self.dirty = true;
@@ -259,6 +315,7 @@ impl Default for Renderer {
             context: None,
             dirty: true,
             antialiasing: Antialiasing::Enabled,
+            offscreen: None,
         }
     }
 }
@@ -267,21 +324,34 @@ unsafe impl Send for Renderer {}
 unsafe impl Sync for Renderer {}
 
 /// Startup system
-pub fn startup(mut renderer: Mut<Renderer>, mut globals: Mut<Globals>, window: Mut<Window>) {
+///
+/// [`Window`] service is not required by the headless [`Renderer`].
+pub fn startup(
+    mut renderer: Mut<Renderer>,
+    mut globals: Mut<Globals>,
+    window: Option<Mut<Window>>,
+) {
     // get sample count
     let sample_count = renderer.antialiasing.sample_count();
     // Init context
     if renderer.context.is_none() {
-        renderer.context = Some(futures::executor::block_on(context::init(
-            window.get(),
-            sample_count,
-        )));
+        renderer.context = Some(if let Some(size) = renderer.offscreen {
+            futures::executor::block_on(context::init_headless(size.x, size.y, sample_count))
+                .expect("Failed to find an appropiate adapter")
+        } else {
+            let window = window.expect("Window service is required to render on screen");
+            futures::executor::block_on(context::init(window.get(), sample_count))
+        });
     }
 
-    // Create texture sampler and store it with Globals
-    let mut sampler = Sampler::default();
-    renderer.load_sampler(&mut sampler);
-    globals.set(sampler);
+    // Load texture sampler, configured by user, or create the default one
+    if let Some(sampler) = globals.get_mut::<Sampler>() {
+        renderer.load_sampler(sampler);
+    } else {
+        let mut sampler = Sampler::default();
+        renderer.load_sampler(&mut sampler);
+        globals.set(sampler);
+    }
 }
 
 /// Frame binding system
@@ -324,7 +394,55 @@ pub fn release(mut renderer: Mut<Renderer>) {
 }
 
 /// Resize handling system
-pub fn resize(mut renderer: Mut<Renderer>, window: Const<Window>) {
-    let size = window.inner_size();
-    renderer.context_mut().resize(size.x, size.y);
+pub fn resize(mut renderer: Mut<Renderer>, window: Option<Const<Window>>) {
+    // offscreen target keeps its size
+    if let (None, Some(window)) = (renderer.offscreen, window) {
+        let size = window.inner_size();
+        renderer.context_mut().resize(size.x, size.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Renderer;
+    use crate::ecs::RunLevel;
+    use crate::{Application, Assets, Color, Globals, State, System};
+
+    #[test]
+    fn headless_without_window() {
+        let mut app = Application::new("Headless");
+        app.add_service(Renderer::headless(4, 4));
+        app.add_system(System::from(super::resize).with(RunLevel::Update));
+
+        // offscreen target is not resized, so no context is needed
+        app.run_frames(2);
+        assert!(app.service::<Renderer>().context.is_none());
+    }
+
+    #[test]
+    #[cfg_attr(
+        not(feature = "gpu-tests"),
+        ignore = "requires a GPU adapter, enable the `gpu-tests` feature"
+    )]
+    fn headless_clear_color() {
+        let mut app = Application::new("Headless");
+        let mut renderer = Renderer::headless(4, 4);
+        renderer.antialiasing = super::Antialiasing::Disabled;
+        renderer.set_clear_color(Color::rgb(1.0, 0.0, 0.0));
+        app.add_service(renderer);
+        app.add_service(Assets::default());
+        app.add_service(Globals::default());
+        app.add_service(State::default());
+        app.add_system(System::from(super::startup));
+        app.add_system(System::from(super::bind));
+        app.add_system(System::from(super::release));
+
+        app.run_frames(2);
+
+        let pixels = app.service::<Renderer>().capture().unwrap();
+        assert_eq!(pixels.len(), 4 * 4 * 4);
+        for pixel in pixels.chunks_exact(4) {
+            assert_eq!(pixel, [255, 0, 0, 255]);
+        }
+    }
 }
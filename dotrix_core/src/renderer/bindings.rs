@@ -1,3 +1,5 @@
+use std::sync::Weak;
+
 use super::{Access, Buffer, Context, PipelineInstance, Sampler, Texture};
 
 /// Rendering stage
@@ -167,10 +169,10 @@ impl<'a> BindGroup<'a> {
                         count: None,
                     }
                 }
-                Binding::Sampler(_, stage, _) => wgpu::BindGroupLayoutEntry {
+                Binding::Sampler(_, stage, sampler) => wgpu::BindGroupLayoutEntry {
                     binding: index as u32,
                     visibility: stage.into(),
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    ty: wgpu::BindingType::Sampler(sampler.binding_type()),
                     count: None,
                 },
                 Binding::Storage(_, stage, storage) => {
@@ -201,6 +203,8 @@ impl<'a> BindGroup<'a> {
 pub struct Bindings {
     /// List of `wgpu::BindGroup`
     pub wgpu_bind_groups: Vec<wgpu::BindGroup>,
+    /// Bound textures, bindings are outdated when any of them gets recreated
+    textures: Vec<Weak<()>>,
 }
 
 impl Bindings {
@@ -214,6 +218,21 @@ impl Bindings {
             PipelineInstance::Render(render) => &render.wgpu_bind_groups_layout,
             PipelineInstance::Compute(compute) => &compute.wgpu_bind_groups_layout,
         };
+        self.textures = bind_groups
+            .iter()
+            .flat_map(|bind_group| bind_group.bindings.iter())
+            .filter_map(|entry| match entry {
+                Binding::Texture(_, _, texture)
+                | Binding::TextureCube(_, _, texture)
+                | Binding::TextureArray(_, _, texture)
+                | Binding::Texture3D(_, _, texture)
+                | Binding::StorageTexture(_, _, texture, _)
+                | Binding::StorageTextureCube(_, _, texture, _)
+                | Binding::StorageTextureArray(_, _, texture, _)
+                | Binding::StorageTexture3D(_, _, texture, _) => Some(texture.instance()),
+                _ => None,
+            })
+            .collect();
         self.wgpu_bind_groups = wgpu_bind_groups_layout
             .iter()
             .enumerate()
@@ -256,13 +275,18 @@ impl Bindings {
             .collect::<Vec<_>>();
     }
 
-    /// Returns true if bindings was loaded to GPU
+    /// Returns true if bindings was loaded to GPU and bound textures were not recreated since
     pub fn loaded(&self) -> bool {
         !self.wgpu_bind_groups.is_empty()
+            && self
+                .textures
+                .iter()
+                .all(|texture| texture.strong_count() > 0)
     }
 
     /// Unloads bindings from GPU
     pub fn unload(&mut self) {
         self.wgpu_bind_groups.clear();
+        self.textures.clear();
     }
 }
@@ -16,8 +16,10 @@ pub struct Context {
     pub device: wgpu::Device,
     /// WGPU Queue
     pub queue: wgpu::Queue,
-    /// WGPU Surface
-    pub surface: wgpu::Surface,
+    /// WGPU Surface, `None` for headless rendering
+    pub surface: Option<wgpu::Surface>,
+    /// Offscreen render target used instead of the surface in headless mode
+    pub offscreen: Option<wgpu::Texture>,
     /// WGPU Surface Configuration
     pub sur_desc: wgpu::SurfaceConfiguration,
     /// Depth Buffer implementation
@@ -37,15 +39,18 @@ pub struct Context {
 impl Context {
     pub(crate) fn bind_frame(&mut self, clear_color: &Color, sample_count: u32) -> bool {
         let mut reload_request = false;
-        let frame = match self.surface.get_current_texture() {
-            Ok(frame) => frame,
-            Err(_) => {
-                self.surface.configure(&self.device, &self.sur_desc);
-                self.surface
-                    .get_current_texture()
-                    .expect("Failed to acquire next surface texture")
-            }
-        };
+        let frame = self
+            .surface
+            .as_ref()
+            .map(|surface| match surface.get_current_texture() {
+                Ok(frame) => frame,
+                Err(_) => {
+                    surface.configure(&self.device, &self.sur_desc);
+                    surface
+                        .get_current_texture()
+                        .expect("Failed to acquire next surface texture")
+                }
+            });
 
         if sample_count != self.sample_count {
             self.multisampled_framebuffer =
@@ -58,7 +63,10 @@ impl Context {
 
         let command_encoder_descriptor = wgpu::CommandEncoderDescriptor { label: None };
         let view = frame
-            .texture
+            .as_ref()
+            .map(|frame| &frame.texture)
+            .or(self.offscreen.as_ref())
+            .expect("Surface or offscreen texture must be set")
             .create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self
             .device
@@ -104,7 +112,7 @@ impl Context {
             });
         }
         self.encoder = Some(encoder);
-        self.frame = Some(frame);
+        self.frame = frame;
         reload_request
     }
 
@@ -122,7 +130,11 @@ impl Context {
             self.sur_desc.width = width;
             self.sur_desc.height = height;
 
-            self.surface.configure(&self.device, &self.sur_desc);
+            if let Some(surface) = self.surface.as_ref() {
+                surface.configure(&self.device, &self.sur_desc);
+            } else {
+                self.offscreen = Some(create_offscreen_texture(&self.device, &self.sur_desc));
+            }
             self.depth_buffer =
                 create_depth_buffer(&self.device, &self.sur_desc, self.sample_count);
             self.multisampled_framebuffer =
@@ -130,6 +142,81 @@ impl Context {
         }
     }
 
+    /// Returns the texture of the current frame: surface texture or offscreen target
+    fn frame_texture(&self) -> &wgpu::Texture {
+        self.frame
+            .as_ref()
+            .map(|frame| &frame.texture)
+            .or(self.offscreen.as_ref())
+            .expect("WGPU frame must be set")
+    }
+
+    /// Returns true if the context renders into the offscreen texture
+    pub fn headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// Reads the offscreen render target back from GPU as tightly packed RGBA8 rows
+    ///
+    /// Returns `None` if the context renders to a window surface
+    pub(crate) fn read_offscreen(&self) -> Option<Vec<u8>> {
+        let texture = self.offscreen.as_ref()?;
+        let width = self.sur_desc.width;
+        let height = self.sur_desc.height;
+        let unpadded_bytes_per_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row_padding = (align - unpadded_bytes_per_row % align) % align;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padded_bytes_per_row_padding;
+
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Staging Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: std::num::NonZeroU32::new(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = staging_buffer.slice(..);
+        let buffer_future = buffer_slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(buffer_future).ok()?;
+
+        let data = buffer_slice.get_mapped_range();
+        let result = data
+            .chunks_exact(padded_bytes_per_row as usize)
+            .flat_map(|row| row[0..(unpadded_bytes_per_row as usize)].iter().copied())
+            .collect::<Vec<u8>>();
+        drop(data);
+        staging_buffer.unmap();
+
+        Some(result)
+    }
+
     pub(crate) fn drop_pipeline(&mut self, shader: Id<Shader>) {
         self.pipelines.remove(&shader);
     }
@@ -161,9 +248,8 @@ impl Context {
             let render_pipeline = pipeline_instance.render();
             let depth_buffer_mode = render_pipeline.depth_buffer_mode;
 
-            let frame = self.frame.as_ref().expect("WGPU frame must be set");
-            let view = frame
-                .texture
+            let view = self
+                .frame_texture()
                 .create_view(&wgpu::TextureViewDescriptor::default());
             let encoder = self.encoder.as_mut().expect("WGPU encoder must be set");
             let rpass_color_attachment = if self.sample_count == 1 {
@@ -316,7 +402,8 @@ pub(crate) async fn init(window: &winit::window::Window, sample_count: u32) -> C
         .request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                features: wgpu::Features::VERTEX_WRITABLE_STORAGE,
+                features: wgpu::Features::VERTEX_WRITABLE_STORAGE
+                    | (adapter.features() & texture_compression()),
                 limits: wgpu::Limits::default(),
             },
             None, // Some(&std::path::Path::new("./wgpu-trace/")),
@@ -343,7 +430,8 @@ pub(crate) async fn init(window: &winit::window::Window, sample_count: u32) -> C
         adapter,
         device,
         queue,
-        surface,
+        surface: Some(surface),
+        offscreen: None,
         sur_desc,
         depth_buffer,
         frame: None,
@@ -354,6 +442,100 @@ pub(crate) async fn init(window: &winit::window::Window, sample_count: u32) -> C
     }
 }
 
+/// Features of compressed texture formats, that are enabled if the adapter supports them
+fn texture_compression() -> wgpu::Features {
+    wgpu::Features::TEXTURE_COMPRESSION_BC
+        | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+        | wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR
+}
+
+/// Initializes the context rendering into an offscreen texture instead of a window surface
+///
+/// The fallback (software) adapter is preferred when available, so results do not depend on
+/// a GPU. Returns `None` if no adapter could be found at all.
+pub(crate) async fn init_headless(width: u32, height: u32, sample_count: u32) -> Option<Context> {
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let fallback_adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: true,
+        })
+        .await;
+    let adapter = match fallback_adapter {
+        Some(adapter) => adapter,
+        None => {
+            instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter: false,
+                })
+                .await?
+        }
+    };
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: adapter.features()
+                    & (wgpu::Features::VERTEX_WRITABLE_STORAGE | texture_compression()),
+                limits: adapter.limits(),
+            },
+            None,
+        )
+        .await
+        .ok()?;
+
+    let sur_desc = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::Fifo,
+    };
+
+    let offscreen = create_offscreen_texture(&device, &sur_desc);
+    let depth_buffer = create_depth_buffer(&device, &sur_desc, sample_count);
+    let multisampled_framebuffer =
+        create_multisampled_framebuffer(&device, &sur_desc, sample_count);
+
+    Some(Context {
+        adapter,
+        device,
+        queue,
+        surface: None,
+        offscreen: Some(offscreen),
+        sur_desc,
+        depth_buffer,
+        frame: None,
+        encoder: None,
+        multisampled_framebuffer,
+        pipelines: std::collections::HashMap::new(),
+        sample_count,
+    })
+}
+
+fn create_offscreen_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+    })
+}
+
 fn create_depth_buffer(
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
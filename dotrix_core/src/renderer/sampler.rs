@@ -1,29 +1,207 @@
+use std::num::NonZeroU8;
+
 use super::Context;
 
 /// Texture Sampler
-#[derive(Default)]
+///
+/// Default sampler repeats textures and uses nearest magnification and linear minification
+/// without blending of mip levels. Custom sampler can be stored in [`crate::Globals`] before
+/// the renderer startup:
+///
+/// ```no_run
+/// use dotrix_core::{renderer::Sampler, Globals};
+///
+/// let mut globals = Globals::default();
+/// globals.set(Sampler::default().anisotropic(16));
+/// ```
+///
+/// Pipelines may also load their own samplers with [`crate::Renderer::load_sampler`] and bind
+/// them instead of the global one:
+///
+/// ```no_run
+/// use dotrix_core::renderer::{wgpu, Sampler};
+///
+/// let sampler = Sampler::new("UI Sampler")
+///     .clamp_to_edge()
+///     .with_mag_filter(wgpu::FilterMode::Linear);
+/// ```
 pub struct Sampler {
+    /// Sampler label
+    pub label: String,
     /// WGPU sampler instance
     pub wgpu_sampler: Option<wgpu::Sampler>,
+    /// Address modes for U, V and W coordinates
+    pub address_modes: [wgpu::AddressMode; 3],
+    /// Filter of magnified textures
+    pub mag_filter: wgpu::FilterMode,
+    /// Filter of minified textures
+    pub min_filter: wgpu::FilterMode,
+    /// Filter between mip levels
+    pub mipmap_filter: wgpu::FilterMode,
+    /// Minimal level of detail
+    pub lod_min_clamp: f32,
+    /// Maximal level of detail
+    pub lod_max_clamp: f32,
+    /// Compare function of a comparison sampler
+    pub compare: Option<wgpu::CompareFunction>,
+    /// Anisotropic filtering clamp
+    pub anisotropy_clamp: Option<NonZeroU8>,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            label: String::from("Sampler"),
+            wgpu_sampler: None,
+            address_modes: [wgpu::AddressMode::Repeat; 3],
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: f32::MAX,
+            compare: None,
+            anisotropy_clamp: None,
+        }
+    }
 }
 
 impl Sampler {
+    /// Constructs new sampler with default settings
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: String::from(label),
+            ..Default::default()
+        }
+    }
+
+    /// Sets address mode for all coordinates
+    #[must_use]
+    pub fn with_address_mode(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_modes = [mode; 3];
+        self
+    }
+
+    /// Sets address modes for U, V and W coordinates
+    #[must_use]
+    pub fn with_address_modes(
+        mut self,
+        u: wgpu::AddressMode,
+        v: wgpu::AddressMode,
+        w: wgpu::AddressMode,
+    ) -> Self {
+        self.address_modes = [u, v, w];
+        self
+    }
+
+    /// Repeats textures
+    #[must_use]
+    pub fn repeat(self) -> Self {
+        self.with_address_mode(wgpu::AddressMode::Repeat)
+    }
+
+    /// Clamps texture coordinates to the edge
+    #[must_use]
+    pub fn clamp_to_edge(self) -> Self {
+        self.with_address_mode(wgpu::AddressMode::ClampToEdge)
+    }
+
+    /// Sets filter of magnified textures
+    #[must_use]
+    pub fn with_mag_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.mag_filter = filter;
+        self
+    }
+
+    /// Sets filter of minified textures
+    #[must_use]
+    pub fn with_min_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.min_filter = filter;
+        self
+    }
+
+    /// Sets filter between mip levels
+    #[must_use]
+    pub fn with_mipmap_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Sets minimal and maximal levels of detail
+    #[must_use]
+    pub fn with_lod_clamp(mut self, min: f32, max: f32) -> Self {
+        self.lod_min_clamp = min;
+        self.lod_max_clamp = max;
+        self
+    }
+
+    /// Makes the sampler a comparison one, for example to sample shadow maps
+    ///
+    /// Comparison samplers are bound as `sampler_comparison` in shaders.
+    #[must_use]
+    pub fn with_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.compare = Some(compare);
+        self
+    }
+
+    /// Sets anisotropic filtering clamp
+    ///
+    /// Valid values of the clamp are 1, 2, 4, 8 and 16. All filters must be linear.
+    #[must_use]
+    pub fn with_anisotropy(mut self, clamp: u8) -> Self {
+        self.anisotropy_clamp = NonZeroU8::new(clamp);
+        self
+    }
+
+    /// Enables trilinear filtering
+    #[must_use]
+    pub fn trilinear(mut self) -> Self {
+        self.mag_filter = wgpu::FilterMode::Linear;
+        self.min_filter = wgpu::FilterMode::Linear;
+        self.mipmap_filter = wgpu::FilterMode::Linear;
+        self
+    }
+
+    /// Enables anisotropic filtering on top of the trilinear one
+    ///
+    /// Valid values of the clamp are 1, 2, 4, 8 and 16.
+    #[must_use]
+    pub fn anisotropic(self, clamp: u8) -> Self {
+        self.trilinear().with_anisotropy(clamp)
+    }
+
     /// Loads the Sampler
     pub(crate) fn load(&mut self, ctx: &Context) {
         if self.wgpu_sampler.is_some() {
             return;
         }
         self.wgpu_sampler = Some(ctx.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
+            label: Some(self.label.as_str()),
+            address_mode_u: self.address_modes[0],
+            address_mode_v: self.address_modes[1],
+            address_mode_w: self.address_modes[2],
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            lod_min_clamp: self.lod_min_clamp,
+            lod_max_clamp: self.lod_max_clamp,
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+            border_color: None,
         }));
     }
 
+    /// Returns type of the sampler binding
+    pub(crate) fn binding_type(&self) -> wgpu::SamplerBindingType {
+        let filters = [self.mag_filter, self.min_filter, self.mipmap_filter];
+        if self.compare.is_some() {
+            wgpu::SamplerBindingType::Comparison
+        } else if filters.contains(&wgpu::FilterMode::Linear) {
+            wgpu::SamplerBindingType::Filtering
+        } else {
+            wgpu::SamplerBindingType::NonFiltering
+        }
+    }
+
     /// Checks if the Sampler is empty
     pub fn loaded(&self) -> bool {
         self.wgpu_sampler.is_some()
@@ -39,3 +217,25 @@ impl Sampler {
         self.wgpu_sampler.as_ref().expect("Sampler must be loaded")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_type() {
+        let sampler = Sampler::new("Nearest").with_min_filter(wgpu::FilterMode::Nearest);
+        assert_eq!(
+            sampler.binding_type(),
+            wgpu::SamplerBindingType::NonFiltering
+        );
+
+        let sampler = sampler.clamp_to_edge().anisotropic(4);
+        assert_eq!(sampler.address_modes, [wgpu::AddressMode::ClampToEdge; 3]);
+        assert_eq!(sampler.anisotropy_clamp, NonZeroU8::new(4));
+        assert_eq!(sampler.binding_type(), wgpu::SamplerBindingType::Filtering);
+
+        let sampler = Sampler::new("Shadows").with_compare(wgpu::CompareFunction::LessEqual);
+        assert_eq!(sampler.binding_type(), wgpu::SamplerBindingType::Comparison);
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::{Arc, Weak};
+
 use super::{Buffer, Context};
 use wgpu;
 
@@ -15,6 +17,10 @@ pub struct Texture {
     pub kind: wgpu::TextureViewDimension,
     /// Texture format
     pub format: wgpu::TextureFormat,
+    /// Width, height and number of mip levels of the created texture
+    extent: (u32, u32, u32),
+    /// Token of the created texture, replaced when the texture gets recreated or unloaded
+    instance: Arc<()>,
 }
 
 impl Default for Texture {
@@ -26,6 +32,8 @@ impl Default for Texture {
             usage: wgpu::TextureUsages::empty(),
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             kind: wgpu::TextureViewDimension::D2,
+            extent: (0, 0, 0),
+            instance: Arc::new(()),
         }
     }
 }
@@ -102,6 +110,30 @@ impl Texture {
         self
     }
 
+    /// Set Texture format to Rgba16Float
+    #[must_use]
+    pub fn rgba_f16(mut self) -> Self {
+        self.format = wgpu::TextureFormat::Rgba16Float;
+        self
+    }
+
+    /// Set Texture format to Rgba32Float
+    #[must_use]
+    pub fn rgba_f32(mut self) -> Self {
+        self.format = wgpu::TextureFormat::Rgba32Float;
+        self
+    }
+
+    /// Set Texture format, e.g. one of block compressed formats
+    ///
+    /// Compressed formats require corresponding device features, see
+    /// [`crate::Renderer::supports_texture_format`].
+    #[must_use]
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     /// Allow to use as Texture
     #[must_use]
     pub fn use_as_texture(mut self) -> Self {
@@ -139,28 +171,69 @@ impl Texture {
 
     /// Loads data into the texture buffer
     ///
-    /// This will recreate the texture backend on the gpu. Which means it must be rebound
-    /// in the pipelines for changes to take effect.
+    /// This will recreate the texture backend on the gpu. Pipelines binding the texture get
+    /// their bindings outdated and are rebound on the next frame.
     ///
     /// If you want to update the values without recreating and therefore rebinding the texture
     /// see `[update]`
     pub(crate) fn load<'a>(&mut self, ctx: &Context, width: u32, height: u32, layers: &[&'a [u8]]) {
-        let dimension = self.kind;
-        if let wgpu::TextureViewDimension::Cube = dimension {
+        if let wgpu::TextureViewDimension::Cube = self.kind {
             assert_eq!(layers.len(), 6);
         };
 
+        self.create(ctx, width, height, layers.len() as u32, 1);
+        self.update(ctx, width, height, layers)
+    }
+
+    /// Loads mip levels of a single layer texture, starting from the base one
+    ///
+    /// Each next level must be twice smaller than the previous one, but at least 1 pixel
+    pub(crate) fn load_levels(&mut self, ctx: &Context, width: u32, height: u32, levels: &[&[u8]]) {
+        self.create(ctx, width, height, 1, levels.len() as u32);
+        self.update_levels(ctx, width, height, levels);
+    }
+
+    /// Rewrites all mip levels of a single layer texture without recreating it
+    ///
+    /// Returns false without writing, if the texture is not loaded or its size or number of
+    /// levels differ from the loaded ones.
+    pub(crate) fn update_levels(
+        &mut self,
+        ctx: &Context,
+        width: u32,
+        height: u32,
+        levels: &[&[u8]],
+    ) -> bool {
+        let texture = match self.wgpu_texture.as_ref() {
+            Some(texture) if self.extent == (width, height, levels.len() as u32) => texture,
+            _ => return false,
+        };
+        for (level, data) in levels.iter().enumerate() {
+            let level = level as u32;
+            let width = (width >> level).max(1);
+            let height = (height >> level).max(1);
+            write(ctx, texture, self.format, level, 0, width, height, data);
+        }
+        true
+    }
+
+    fn create(
+        &mut self,
+        ctx: &Context,
+        width: u32,
+        height: u32,
+        depth_or_array_layers: u32,
+        mip_level_count: u32,
+    ) {
+        let dimension = self.kind;
         let format = self.format;
         let usage = self.usage;
-        let depth_or_array_layers = layers.len() as u32;
         let size = wgpu::Extent3d {
             width,
             height,
             depth_or_array_layers,
         };
 
-        let max_mips = 1;
-
         let tex_dimension: wgpu::TextureDimension = match self.kind {
             wgpu::TextureViewDimension::D2 => wgpu::TextureDimension::D2,
             wgpu::TextureViewDimension::Cube => wgpu::TextureDimension::D2,
@@ -172,7 +245,7 @@ impl Texture {
         let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
             label: Some(&self.label),
             size,
-            mip_level_count: max_mips as u32,
+            mip_level_count,
             sample_count: 1,
             dimension: tex_dimension,
             format,
@@ -187,8 +260,8 @@ impl Texture {
         }));
 
         self.wgpu_texture = Some(texture);
-
-        self.update(ctx, width, height, layers)
+        self.extent = (width, height, mip_level_count);
+        self.instance = Arc::new(());
     }
 
     /// This will write to a texture but not create it
@@ -205,38 +278,8 @@ impl Texture {
         layers: &[&'a [u8]],
     ) {
         if let Some(texture) = self.wgpu_texture.as_ref() {
-            let depth_or_array_layers = layers.len() as u32;
-            let size = wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers,
-            };
-            let layer_size = wgpu::Extent3d {
-                depth_or_array_layers: 1,
-                ..size
-            };
-
             for (i, data) in layers.iter().enumerate() {
-                let bytes_per_row = std::num::NonZeroU32::new(data.len() as u32 / height).unwrap();
-                ctx.queue.write_texture(
-                    wgpu::ImageCopyTexture {
-                        texture,
-                        mip_level: 0,
-                        origin: wgpu::Origin3d {
-                            x: 0,
-                            y: 0,
-                            z: i as u32,
-                        },
-                        aspect: wgpu::TextureAspect::All,
-                    },
-                    data,
-                    wgpu::ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(bytes_per_row),
-                        rows_per_image: Some(std::num::NonZeroU32::new(height).unwrap()),
-                    },
-                    layer_size,
-                );
+                write(ctx, texture, self.format, 0, i as u32, width, height, data);
             }
         }
     }
@@ -269,6 +312,12 @@ impl Texture {
     pub fn unload(&mut self) {
         self.wgpu_texture.take();
         self.wgpu_texture_view.take();
+        self.instance = Arc::new(());
+    }
+
+    /// Returns a reference, that becomes dead when the texture gets recreated or unloaded
+    pub(crate) fn instance(&self) -> Weak<()> {
+        Arc::downgrade(&self.instance)
     }
 
     /// Get unwrapped reference to WGPU Texture View
@@ -369,3 +418,62 @@ impl Texture {
         }
     }
 }
+
+/// Writes data of a mip level of a texture layer
+///
+/// Rows of block compressed formats are rows of blocks, not pixels
+#[allow(clippy::too_many_arguments)]
+fn write(
+    ctx: &Context,
+    texture: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+    mip_level: u32,
+    layer: u32,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) {
+    let (block_width, block_height) = format.describe().block_dimensions;
+    let rows = height.div_ceil(block_height as u32);
+    let bytes_per_row = std::num::NonZeroU32::new(data.len() as u32 / rows).unwrap();
+    ctx.queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level,
+            origin: wgpu::Origin3d {
+                x: 0,
+                y: 0,
+                z: layer,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(bytes_per_row),
+            rows_per_image: std::num::NonZeroU32::new(rows),
+        },
+        // copies of compressed textures are aligned to blocks
+        wgpu::Extent3d {
+            width: width.div_ceil(block_width as u32) * block_width as u32,
+            height: rows * block_height as u32,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outdated_instance() {
+        let mut texture = Texture::new("Texture");
+        let instance = texture.instance();
+        assert_eq!(instance.strong_count(), 1);
+
+        texture.unload();
+        assert_eq!(instance.strong_count(), 0);
+        assert_eq!(texture.instance().strong_count(), 1);
+    }
+}
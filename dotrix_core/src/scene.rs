@@ -0,0 +1,540 @@
+//! World scenes serialization
+//!
+//! A [`Scene`] is a serializable snapshot of the [`World`] entities. Only components registered
+//! in the [`Scenes`] service are stored. References to assets are stored by their names, so
+//! the scene can be loaded in another session, when assets get other [`Id`]s.
+//!
+//! ```no_run
+//! use dotrix_core::{
+//!     ecs::{Const, Mut},
+//!     scene::{Scene, Scenes},
+//!     Assets, World,
+//! };
+//!
+//! fn save(world: Const<World>, assets: Const<Assets>, scenes: Const<Scenes>) {
+//!     let scene = scenes.save(&world, &assets);
+//!     scene.write("saves/level.json").expect("Scene must be saved");
+//! }
+//!
+//! fn load(mut world: Mut<World>, mut assets: Mut<Assets>, scenes: Const<Scenes>) {
+//!     let scene = Scene::read("saves/level.json").expect("Scene must be loaded");
+//!     scenes.spawn(&scene, &mut world, &mut assets).expect("Scene must be spawned");
+//! }
+//! ```
+use std::collections::{BTreeMap, HashMap};
+
+use log::warn;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::assets::{AssetMapGetter, Shader, Texture};
+use crate::ecs::{Component, Entity};
+use crate::renderer::{Pipeline, Render};
+use crate::{Assets, CubeMap, Id, Parent, Transform, World};
+
+/// Serialized representation of entities
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    /// List of serialized entities
+    pub entities: Vec<Node>,
+}
+
+/// Serialized entity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    /// Entity identifier at the moment of saving
+    pub id: u64,
+    /// Serialized components by their registered names
+    pub components: BTreeMap<String, serde_json::Value>,
+}
+
+/// Scene errors
+#[derive(Debug)]
+pub enum SceneError {
+    /// Error occured during file reading or writing
+    File(std::io::Error),
+    /// Error occured during (de)serialization
+    Json(serde_json::Error),
+    /// Component name was not registered in [`Scenes`]
+    UnknownComponent(String),
+}
+
+impl std::error::Error for SceneError {}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::File(err) => write!(f, "Scene file error: {}", err),
+            SceneError::Json(err) => write!(f, "Scene format error: {}", err),
+            SceneError::UnknownComponent(name) => write!(f, "Unknown scene component: {}", name),
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::File(err)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(err: serde_json::Error) -> Self {
+        SceneError::Json(err)
+    }
+}
+
+impl Scene {
+    /// Parses the scene from JSON string
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serializes the scene into JSON string
+    pub fn to_json(&self) -> Result<String, SceneError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Reads the scene from JSON file
+    pub fn read<P: AsRef<std::path::Path>>(path: P) -> Result<Self, SceneError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes the scene to JSON file
+    pub fn write<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), SceneError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+/// Component, that can be stored in a [`Scene`]
+///
+/// Components that implement [`serde::Serialize`] and [`serde::Deserialize`] and don't
+/// reference assets or entities can be registered with [`Scenes::register_serde`] instead.
+pub trait Serializable: Component + Sized {
+    /// Serializable representation of the component
+    type Data: Serialize + DeserializeOwned;
+    /// Converts the component into serializable representation
+    fn save(&self, context: &SaveContext) -> Self::Data;
+    /// Restores the component from serializable representation
+    ///
+    /// If `None` is returned, the entity is spawned without the component, for example, when
+    /// the component refers to an entity, that is not part of the scene.
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self>;
+}
+
+/// Context of the scene saving
+pub struct SaveContext<'a> {
+    assets: &'a Assets,
+}
+
+impl<'a> SaveContext<'a> {
+    /// Returns name of the asset or `None` if the asset has no name
+    pub fn asset<T>(&self, id: Id<T>) -> Option<String>
+    where
+        Assets: AssetMapGetter<T>,
+    {
+        self.assets.name(id).map(String::from)
+    }
+
+    /// Returns serializable identifier of the entity
+    pub fn entity(&self, entity: Entity) -> u64 {
+        entity.into()
+    }
+}
+
+/// Context of the scene loading
+pub struct LoadContext<'a> {
+    assets: &'a mut Assets,
+    entities: HashMap<u64, Entity>,
+}
+
+impl<'a> LoadContext<'a> {
+    /// Returns [`Id`] of the asset by its name
+    ///
+    /// Asset that is not loaded yet gets registered, so the [`Id`] becomes valid once the asset
+    /// is loaded. Null [`Id`] is returned for `None`.
+    pub fn asset<T>(&mut self, name: Option<&str>) -> Id<T>
+    where
+        Assets: AssetMapGetter<T>,
+    {
+        match name {
+            Some(name) => self
+                .assets
+                .find(name)
+                .unwrap_or_else(|| self.assets.register(name)),
+            None => Id::default(),
+        }
+    }
+
+    /// Returns spawned entity by its serialized identifier or `None`, if the entity is not part
+    /// of the scene
+    pub fn entity(&self, id: u64) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+}
+
+type SaveFn = fn(&World, &SaveContext) -> Result<Vec<(u64, serde_json::Value)>, SceneError>;
+type LoadFn = fn(&mut World, Entity, serde_json::Value, &mut LoadContext) -> Result<(), SceneError>;
+
+struct Registration {
+    save: SaveFn,
+    load: LoadFn,
+}
+
+/// Scenes service with a registry of serializable components
+pub struct Scenes {
+    registry: BTreeMap<String, Registration>,
+}
+
+impl Scenes {
+    /// Constructs the service with registered core components
+    pub fn new() -> Self {
+        let mut scenes = Self {
+            registry: BTreeMap::new(),
+        };
+        scenes.register_serde::<Transform>("dotrix::Transform");
+        scenes.register::<Parent>("dotrix::Parent");
+        scenes.register::<CubeMap>("dotrix::CubeMap");
+        scenes.register::<Render>("dotrix::Render");
+        scenes
+    }
+
+    /// Registers a [`Serializable`] component under the name
+    pub fn register<T: Serializable>(&mut self, name: &str) {
+        self.registry.insert(
+            String::from(name),
+            Registration {
+                save: save_component::<T>,
+                load: load_component::<T>,
+            },
+        );
+    }
+
+    /// Registers a component implementing [`serde::Serialize`] and [`serde::Deserialize`]
+    pub fn register_serde<T>(&mut self, name: &str)
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.registry.insert(
+            String::from(name),
+            Registration {
+                save: save_serde::<T>,
+                load: load_serde::<T>,
+            },
+        );
+    }
+
+    /// Returns true if a component was registered under the name
+    pub fn registered(&self, name: &str) -> bool {
+        self.registry.contains_key(name)
+    }
+
+    /// Saves registered components of all entities in the [`World`]
+    ///
+    /// Entities without registered components are skipped.
+    pub fn save(&self, world: &World, assets: &Assets) -> Scene {
+        self.try_save(world, assets)
+            .expect("Registered components must be serializable")
+    }
+
+    /// Saves registered components of all entities in the [`World`] or returns an error if some
+    /// component can't be serialized
+    pub fn try_save(&self, world: &World, assets: &Assets) -> Result<Scene, SceneError> {
+        let context = SaveContext { assets };
+        let mut nodes: BTreeMap<u64, Node> = BTreeMap::new();
+        for (name, registration) in self.registry.iter() {
+            for (id, value) in (registration.save)(world, &context)? {
+                nodes
+                    .entry(id)
+                    .or_insert_with(|| Node {
+                        id,
+                        ..Default::default()
+                    })
+                    .components
+                    .insert(name.clone(), value);
+            }
+        }
+        Ok(Scene {
+            entities: nodes.into_values().collect(),
+        })
+    }
+
+    /// Spawns entities of the [`Scene`] into the [`World`] and returns them
+    ///
+    /// Entities get new identifiers, references between entities of the scene are updated. If
+    /// some component can't be loaded, spawned entities are exiled and the error is returned.
+    pub fn spawn(
+        &self,
+        scene: &Scene,
+        world: &mut World,
+        assets: &mut Assets,
+    ) -> Result<Vec<Entity>, SceneError> {
+        for node in scene.entities.iter() {
+            if let Some(name) = node.components.keys().find(|name| !self.registered(name)) {
+                return Err(SceneError::UnknownComponent(name.clone()));
+            }
+        }
+
+        let mut context = LoadContext {
+            assets,
+            entities: HashMap::new(),
+        };
+        let mut spawned = Vec::with_capacity(scene.entities.len());
+        for node in scene.entities.iter() {
+            let entity = world.spawn(Some(())).first();
+            context.entities.insert(node.id, entity);
+            spawned.push(entity);
+        }
+
+        for (node, entity) in scene.entities.iter().zip(spawned.iter()) {
+            for (name, value) in node.components.iter() {
+                let registration = &self.registry[name];
+                if let Err(error) = (registration.load)(world, *entity, value.clone(), &mut context)
+                {
+                    // do not leave partially loaded scene in the world
+                    for entity in spawned.iter() {
+                        world.exile(*entity);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(spawned)
+    }
+}
+
+impl Default for Scenes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn save_component<T: Serializable>(
+    world: &World,
+    context: &SaveContext,
+) -> Result<Vec<(u64, serde_json::Value)>, SceneError> {
+    world
+        .query::<(&Entity, &T)>()
+        .map(|(entity, component)| {
+            Ok((
+                context.entity(*entity),
+                serde_json::to_value(component.save(context))?,
+            ))
+        })
+        .collect()
+}
+
+fn load_component<T: Serializable>(
+    world: &mut World,
+    entity: Entity,
+    value: serde_json::Value,
+    context: &mut LoadContext,
+) -> Result<(), SceneError> {
+    let data: T::Data = serde_json::from_value(value)?;
+    if let Some(component) = T::load(data, context) {
+        world.insert(entity, component);
+    }
+    Ok(())
+}
+
+fn save_serde<T: Component + Serialize>(
+    world: &World,
+    context: &SaveContext,
+) -> Result<Vec<(u64, serde_json::Value)>, SceneError> {
+    world
+        .query::<(&Entity, &T)>()
+        .map(|(entity, component)| Ok((context.entity(*entity), serde_json::to_value(component)?)))
+        .collect()
+}
+
+fn load_serde<T: Component + DeserializeOwned>(
+    world: &mut World,
+    entity: Entity,
+    value: serde_json::Value,
+    _context: &mut LoadContext,
+) -> Result<(), SceneError> {
+    let component: T = serde_json::from_value(value)?;
+    world.insert(entity, component);
+    Ok(())
+}
+
+impl Serializable for Parent {
+    type Data = u64;
+
+    fn save(&self, context: &SaveContext) -> Self::Data {
+        context.entity(self.0)
+    }
+
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self> {
+        let parent = context.entity(data);
+        if parent.is_none() {
+            warn!("Parent entity {} is not part of the scene", data);
+        }
+        parent.map(Parent)
+    }
+}
+
+/// Serializable representation of the [`CubeMap`]
+#[derive(Serialize, Deserialize)]
+pub struct CubeMapData {
+    right: Option<String>,
+    left: Option<String>,
+    top: Option<String>,
+    bottom: Option<String>,
+    back: Option<String>,
+    front: Option<String>,
+}
+
+impl Serializable for CubeMap {
+    type Data = CubeMapData;
+
+    fn save(&self, context: &SaveContext) -> Self::Data {
+        CubeMapData {
+            right: context.asset::<Texture>(self.right),
+            left: context.asset::<Texture>(self.left),
+            top: context.asset::<Texture>(self.top),
+            bottom: context.asset::<Texture>(self.bottom),
+            back: context.asset::<Texture>(self.back),
+            front: context.asset::<Texture>(self.front),
+        }
+    }
+
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self> {
+        Some(CubeMap {
+            right: context.asset(data.right.as_deref()),
+            left: context.asset(data.left.as_deref()),
+            top: context.asset(data.top.as_deref()),
+            bottom: context.asset(data.bottom.as_deref()),
+            back: context.asset(data.back.as_deref()),
+            front: context.asset(data.front.as_deref()),
+            ..Default::default()
+        })
+    }
+}
+
+/// Serializable representation of the [`Render`] component
+#[derive(Serialize, Deserialize)]
+pub struct RenderData {
+    shader: Option<String>,
+    disabled: bool,
+}
+
+impl Serializable for Render {
+    type Data = RenderData;
+
+    fn save(&self, context: &SaveContext) -> Self::Data {
+        RenderData {
+            shader: context.asset::<Shader>(self.pipeline.shader),
+            disabled: self.pipeline.disabled,
+        }
+    }
+
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self> {
+        let mut render = Pipeline::render(context.asset(data.shader.as_deref()));
+        render.pipeline.disabled = data.disabled;
+        Some(render)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scene, SceneError, Scenes};
+    use crate::assets::Texture;
+    use crate::ecs::Entity;
+    use crate::{Assets, CubeMap, Parent, Transform, World};
+    use dotrix_math::Vec3;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Health(u32);
+
+    struct Temporary;
+
+    #[test]
+    fn save_and_spawn() {
+        let mut assets = Assets::new();
+        let mut scenes = Scenes::new();
+        scenes.register_serde::<Health>("test::Health");
+
+        let sky = assets.register::<Texture>("sky");
+        let mut world = World::new();
+        world.spawn(Some((Temporary,)));
+        let root = world
+            .spawn(Some((
+                Transform::from_translation(Vec3::new(1.0, 2.0, 3.0)),
+                Health(100),
+            )))
+            .first();
+        world.spawn(Some((
+            Parent(root),
+            CubeMap {
+                top: sky,
+                ..Default::default()
+            },
+        )));
+
+        let json = scenes.save(&world, &assets).to_json().unwrap();
+        let scene = Scene::from_json(&json).unwrap();
+        assert_eq!(scene.entities.len(), 2);
+
+        let mut world = World::new();
+        world.spawn(Some((Health(1),)));
+        let mut assets = Assets::new();
+        let entities = scenes.spawn(&scene, &mut world, &mut assets).unwrap();
+        assert_eq!(entities, vec![Entity::from(1), Entity::from(2)]);
+
+        let (transform, health) = world.get::<(&Transform, &Health)>(entities[0]).unwrap();
+        assert_eq!(transform.translate, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(health, &Health(100));
+
+        let (parent, cubemap) = world.get::<(&Parent, &CubeMap)>(entities[1]).unwrap();
+        assert_eq!(parent, &Parent(entities[0]));
+        assert_eq!(Some(cubemap.top), assets.find::<Texture>("sky"));
+        assert!(cubemap.left.is_null());
+    }
+
+    #[test]
+    fn unknown_component() {
+        let scenes = Scenes::new();
+        let scene =
+            Scene::from_json(r#"{"entities":[{"id":0,"components":{"Unknown":null}}]}"#).unwrap();
+        let result = scenes.spawn(&scene, &mut World::new(), &mut Assets::new());
+        assert!(matches!(result, Err(SceneError::UnknownComponent(name)) if name == "Unknown"));
+    }
+
+    #[test]
+    fn invalid_component() {
+        let mut scenes = Scenes::new();
+        scenes.register_serde::<Health>("test::Health");
+        let scene = Scene::from_json(
+            r#"{"entities":[
+                {"id":0,"components":{"test::Health":100}},
+                {"id":1,"components":{"test::Health":"full"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        let result = scenes.spawn(&scene, &mut world, &mut Assets::new());
+        assert!(matches!(result, Err(SceneError::Json(_))));
+        assert_eq!(world.query::<(&Entity,)>().count(), 0);
+    }
+
+    #[test]
+    fn external_parent() {
+        let mut scenes = Scenes::new();
+        scenes.register_serde::<Health>("test::Health");
+        let scene = Scene::from_json(
+            r#"{"entities":[{"id":0,"components":{"dotrix::Parent":7,"test::Health":100}}]}"#,
+        )
+        .unwrap();
+
+        // reference to an entity outside of the scene is left unset
+        let mut world = World::new();
+        world.spawn((0..8).map(|_| (Health(1),)));
+        let entities = scenes
+            .spawn(&scene, &mut world, &mut Assets::new())
+            .unwrap();
+        assert_eq!(world.get::<(&Health,)>(entities[0]), Some((&Health(100),)));
+        assert!(world.get::<(&Parent,)>(entities[0]).is_none());
+    }
+}
@@ -11,6 +11,24 @@ pub enum Rule {
     StateOn(Id<State>),
     /// System does not run at specific state
     StateOff(Id<State>),
+    /// System runs once, when the state is pushed to the stack
+    OnEnter(Id<State>),
+    /// System runs once, when the state is popped from the stack
+    OnExit(Id<State>),
+    /// System runs once, when another state is pushed over the state
+    OnPause(Id<State>),
+    /// System runs once, when the state becomes current again after the pop of another one
+    OnResume(Id<State>),
+}
+
+impl Rule {
+    /// Returns true if the rule defines a state transition hook
+    pub fn is_hook(&self) -> bool {
+        matches!(
+            self,
+            Rule::OnEnter(_) | Rule::OnExit(_) | Rule::OnPause(_) | Rule::OnResume(_)
+        )
+    }
 }
 
 struct Entry {
@@ -28,6 +46,8 @@ struct Meta;
 pub struct State {
     stack: Vec<Entry>,
     state_ptr: *const Id<State>,
+    /// Transitions, that hooks were not fired for yet
+    transitions: Vec<Rule>,
 }
 
 // Secured by state_ptr controls
@@ -59,6 +79,31 @@ impl State {
         Rule::StateOff(Id::of::<T>())
     }
 
+    /// Returns a rule, so the system will run once, when the state is pushed
+    pub fn on_enter<T: IntoState>() -> Rule {
+        Rule::OnEnter(Id::of::<T>())
+    }
+
+    /// Returns a rule, so the system will run once, when the state is popped
+    pub fn on_exit<T: IntoState>() -> Rule {
+        Rule::OnExit(Id::of::<T>())
+    }
+
+    /// Returns a rule, so the system will run once, when another state is pushed over the state
+    pub fn on_pause<T: IntoState>() -> Rule {
+        Rule::OnPause(Id::of::<T>())
+    }
+
+    /// Returns a rule, so the system will run once, when the state becomes current again
+    pub fn on_resume<T: IntoState>() -> Rule {
+        Rule::OnResume(Id::of::<T>())
+    }
+
+    /// Takes transitions, that hooks were not fired for yet
+    pub(crate) fn take_transitions(&mut self) -> Vec<Rule> {
+        std::mem::take(&mut self.transitions)
+    }
+
     /// Pushes the application state to the stack
     pub fn push<T>(&mut self, state: T)
     where
@@ -66,6 +111,10 @@ impl State {
     {
         let state_id: Id<State> = Id::of::<T>();
         let name = String::from(std::any::type_name::<T>());
+        if let Some(paused) = self.id() {
+            self.transitions.push(Rule::OnPause(paused));
+        }
+        self.transitions.push(Rule::OnEnter(state_id));
         self.stack.push(Entry {
             state_id,
             name,
@@ -93,6 +142,12 @@ impl State {
     /// Pops the application state from the stack, but do not downcast it
     pub fn pop_any(&mut self) -> Option<Box<dyn IntoState>> {
         let last = self.stack.pop();
+        if let Some(entry) = last.as_ref() {
+            self.transitions.push(Rule::OnExit(entry.state_id));
+            if let Some(resumed) = self.id() {
+                self.transitions.push(Rule::OnResume(resumed));
+            }
+        }
         let state_id = self
             .stack
             .last()
@@ -133,7 +188,9 @@ impl State {
 
     /// Clears states stack
     pub fn clear(&mut self) {
-        self.stack.clear();
+        while let Some(entry) = self.stack.pop() {
+            self.transitions.push(Rule::OnExit(entry.state_id));
+        }
         self.write_pointer(Self::meta());
     }
 
@@ -148,6 +205,7 @@ impl Default for State {
         Self {
             stack: vec![],
             state_ptr: std::ptr::null(),
+            transitions: vec![],
         }
     }
 }
@@ -239,4 +297,41 @@ mod tests {
         let state_id = unsafe { *(state.state_ptr as *const Id<State>) };
         assert_eq!(state_id, Id::of::<Meta>());
     }
+
+    #[test]
+    fn state_transitions() {
+        let mut state = State::default();
+
+        state.push(SimpleState {});
+        state.push(StateWithData(123));
+        assert_eq!(
+            state.take_transitions(),
+            vec![
+                State::on_enter::<SimpleState>(),
+                State::on_pause::<SimpleState>(),
+                State::on_enter::<StateWithData>(),
+            ]
+        );
+
+        state.pop_any();
+        assert_eq!(
+            state.take_transitions(),
+            vec![
+                State::on_exit::<StateWithData>(),
+                State::on_resume::<SimpleState>(),
+            ]
+        );
+
+        state.push(StateWithData(123));
+        state.take_transitions();
+        state.clear();
+        assert_eq!(
+            state.take_transitions(),
+            vec![
+                State::on_exit::<StateWithData>(),
+                State::on_exit::<SimpleState>(),
+            ]
+        );
+        assert!(state.take_transitions().is_empty());
+    }
 }
@@ -0,0 +1,57 @@
+//! Asynchronous tasks service
+use std::{future::Future, pin::Pin, sync::Mutex};
+
+/// Boxed future of a task
+pub type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Asynchronous tasks service
+///
+/// Tasks are executed on the main thread by the application pool, once per frame, after
+/// loaded assets were fetched. They are useful for logic, that awaits something, like
+/// [`crate::Assets::load_async`]:
+///
+/// ```no_run
+/// use dotrix_core::{assets::Texture, ecs::Mut, Assets, Tasks};
+///
+/// fn startup(mut assets: Mut<Assets>, mut tasks: Mut<Tasks>) {
+///     let texture = assets.load_async::<Texture>("textures/ground.png");
+///     tasks.spawn(async move {
+///         match texture.await {
+///             Ok(id) => println!("Texture is ready: {:?}", id),
+///             Err(e) => println!("Texture import failed: {}", e),
+///         }
+///     });
+/// }
+/// ```
+#[derive(Default)]
+pub struct Tasks {
+    // futures are only moved between threads, the mutex makes the service `Sync`
+    queue: Mutex<Vec<Task>>,
+}
+
+impl Tasks {
+    /// Constructs new [`Tasks`] service
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a task
+    ///
+    /// The task starts on the next run of the application pool.
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.queue.get_mut().unwrap().push(Box::pin(future));
+    }
+
+    /// Returns number of tasks, that were spawned but not started yet
+    pub fn queued(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    /// Takes spawned tasks out of the queue
+    pub(crate) fn take(&mut self) -> Vec<Task> {
+        std::mem::take(self.queue.get_mut().unwrap())
+    }
+}
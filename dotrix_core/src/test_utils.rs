@@ -0,0 +1,64 @@
+//! Fixtures shared by unit tests
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::Assets;
+
+/// Time to wait for background work, e.g. importing of assets
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Temporary directory, that is removed with its content when dropped
+///
+/// The directory is removed even if the test panics.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory, unique for the test process
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "dotrix-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("Temporary directory must be created");
+        Self { path }
+    }
+
+    /// Returns path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns path of a file in the directory
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Calls the function until it returns true, panics if it does not happen in time
+pub fn poll<F: FnMut() -> bool>(mut done: F) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !done() {
+        assert!(Instant::now() < deadline, "Condition was not met in time");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Fetches imported assets until the condition is true, panics if it does not happen in time
+pub fn fetch_until<F: FnMut(&Assets) -> bool>(assets: &mut Assets, mut done: F) {
+    poll(|| {
+        assets.fetch();
+        done(assets)
+    });
+}
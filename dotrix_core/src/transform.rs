@@ -1,5 +1,15 @@
 //! Transformation structure and builder
-use dotrix_math::{Mat4, Quat, Rad, Rotation3, Vec3};
+//!
+//! Entities can be organized into hierarchies using [`Parent`] components. The [`propagate`]
+//! system calculates [`GlobalTransform`] of each entity combining its [`Transform`] with the
+//! [`GlobalTransform`] of its parent, so children follow their parents.
+use std::collections::{HashMap, HashSet};
+
+use dotrix_math::{Mat4, Quat, Rad, Rotation3, SquareMatrix, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+use crate::ecs::{Entity, Mut, Priority, RunLevel, System, Without, Write};
+use crate::{Application, World};
 
 /// Agregator for skin transformations
 #[derive(Default)]
@@ -51,7 +61,7 @@ impl Builder {
 }
 
 /// Model transformation structure
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Transform {
     /// Translation vector
     pub translate: Vec3,
@@ -140,3 +150,234 @@ impl Default for Transform {
         Self::new()
     }
 }
+
+/// Component, that attaches the entity to a parent one
+///
+/// [`Transform`] of the entity becomes relative to the parent
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Parent(pub Entity);
+
+/// Component with list of entities attached to the entity
+///
+/// The list is maintained by the [`propagate`] system from the [`Parent`] components
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Children(pub Vec<Entity>);
+
+/// World space transformation of the entity
+///
+/// The component is inserted and calculated by the [`propagate`] system for every entity with
+/// [`Transform`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GlobalTransform {
+    /// World space transformation matrix
+    pub matrix: Mat4,
+}
+
+impl GlobalTransform {
+    /// Returns world space transformation matrix
+    pub fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+
+    /// Returns world space position
+    pub fn translation(&self) -> Vec3 {
+        let w: Vec4 = self.matrix.w;
+        Vec3::new(w.x, w.y, w.z)
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Mat4::identity(),
+        }
+    }
+}
+
+/// Adds the [`propagate`] system to the application, unless it was already added
+///
+/// Extensions rendering entities by their [`GlobalTransform`] call it to not depend on the
+/// application setup. Note, that the system walks all the hierarchies every frame.
+pub fn extension(app: &mut Application) {
+    if !app.has_system("dotrix_core::transform::propagate") {
+        app.add_system(
+            System::from(propagate)
+                .with(RunLevel::Load)
+                .with(Priority::High),
+        );
+    }
+}
+
+/// Transformations propagation system
+///
+/// Synchronizes [`Children`] with [`Parent`] components and calculates [`GlobalTransform`] of
+/// entities from roots to leaves. Entities without [`Transform`] pass the transformation of
+/// their parents to children as is. Entities, which parent does not exist, are roots.
+///
+/// All the hierarchies are walked every frame, so the cost is O(N) of entities with [`Parent`]
+/// or [`Transform`], even if nothing has moved. Only [`GlobalTransform`]s, that really change,
+/// are written and marked as changed, so systems filtering them by
+/// [`crate::ecs::Changed`] are not affected.
+pub fn propagate(mut world: Mut<World>) {
+    // Synchronize children lists
+    let mut children: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (entity, parent) in world.query::<(&Entity, &Parent)>() {
+        children.entry(parent.0).or_default().push(*entity);
+    }
+
+    let obsolete = world
+        .query::<(&Entity, &Children)>()
+        .filter(|(entity, list)| children.get(entity) != Some(&list.0))
+        .map(|(entity, _)| *entity)
+        .collect::<Vec<_>>();
+    for entity in obsolete {
+        if !children.contains_key(&entity) {
+            world.remove::<Children>(entity);
+        }
+    }
+    for (parent, list) in children.iter() {
+        let same = world
+            .get::<(&Children,)>(*parent)
+            .map(|(current,)| &current.0 == list)
+            .unwrap_or(false);
+        if !same {
+            world.insert(*parent, Children(list.clone()));
+        }
+    }
+
+    // Add missing global transformations
+    let missing = world
+        .query_filtered::<(&Entity, &Transform), Without<GlobalTransform>>()
+        .map(|(entity, _)| *entity)
+        .collect::<Vec<_>>();
+    for entity in missing {
+        world.insert(entity, GlobalTransform::default());
+    }
+
+    // Calculate global transformations
+    let world = &*world;
+    let mut stack = world
+        .query::<(&Entity,)>()
+        .filter(|(entity,)| {
+            world
+                .get::<(&Parent,)>(**entity)
+                .map(|(parent,)| world.get::<(&Entity,)>(parent.0).is_none())
+                .unwrap_or(true)
+        })
+        .map(|(entity,)| (*entity, Mat4::identity()))
+        .collect::<Vec<_>>();
+
+    let mut visited = HashSet::new();
+    while let Some((entity, parent_matrix)) = stack.pop() {
+        if !visited.insert(entity) {
+            continue;
+        }
+        let matrix = match world.get::<(&Transform, Write<GlobalTransform>)>(entity) {
+            Some((transform, mut global_transform)) => {
+                let matrix = parent_matrix * transform.matrix();
+                // keep unchanged transforms out of the `Changed` filter
+                if global_transform.matrix != matrix {
+                    global_transform.matrix = matrix;
+                }
+                matrix
+            }
+            None => parent_matrix,
+        };
+        if let Some((children,)) = world.get::<(&Children,)>(entity) {
+            stack.extend(children.0.iter().map(|child| (*child, matrix)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extension, propagate, Children, GlobalTransform, Parent, Transform};
+    use crate::application::Services;
+    use crate::ecs::{After, Const, Entity, RunLevel, System};
+    use crate::Application;
+    use crate::{State, World};
+    use dotrix_math::Vec3;
+
+    fn position(services: &Services, entity: Entity) -> Option<Vec3> {
+        services
+            .get::<World>()
+            .unwrap()
+            .get::<(&GlobalTransform,)>(entity)
+            .map(|(global_transform,)| global_transform.translation())
+    }
+
+    fn children(services: &Services, entity: Entity) -> Option<Vec<Entity>> {
+        services
+            .get::<World>()
+            .unwrap()
+            .get::<(&Children,)>(entity)
+            .map(|(children,)| children.0.clone())
+    }
+
+    #[test]
+    fn hierarchy_propagation() {
+        let mut services = Services::new();
+        let mut system = System::from(propagate);
+        let mut world = World::new();
+        let car = world
+            .spawn(Some((Transform::from_translation(Vec3::new(
+                10.0, 0.0, 0.0,
+            )),)))
+            .first();
+        let wheel = world
+            .spawn(Some((
+                Transform::from_translation(Vec3::new(1.0, 0.0, 1.0)),
+                Parent(car),
+            )))
+            .first();
+        // group without transformation
+        let group = world.spawn(Some((Parent(wheel),))).first();
+        let bolt = world
+            .spawn(Some((
+                Transform::from_translation(Vec3::new(0.0, 0.5, 0.0)),
+                Parent(group),
+            )))
+            .first();
+        services.add(world);
+
+        system.data.run(&services, State::meta());
+
+        assert_eq!(children(&services, car), Some(vec![wheel]));
+        assert_eq!(position(&services, car), Some(Vec3::new(10.0, 0.0, 0.0)));
+        assert_eq!(position(&services, wheel), Some(Vec3::new(11.0, 0.0, 1.0)));
+        assert_eq!(position(&services, group), None);
+        assert_eq!(position(&services, bolt), Some(Vec3::new(11.0, 0.5, 1.0)));
+
+        let world = services.get_mut::<World>().unwrap();
+        world.insert(car, Transform::from_translation(Vec3::new(0.0, 2.0, 0.0)));
+        world.insert(bolt, Parent(car));
+        system.data.run(&services, State::meta());
+
+        assert_eq!(children(&services, car).map(|list| list.len()), Some(2));
+        assert_eq!(children(&services, group), None);
+        assert_eq!(position(&services, wheel), Some(Vec3::new(1.0, 2.0, 1.0)));
+        assert_eq!(position(&services, bolt), Some(Vec3::new(0.0, 2.5, 0.0)));
+
+        // orphans become roots
+        services.get_mut::<World>().unwrap().exile(car);
+        system.data.run(&services, State::meta());
+        assert_eq!(position(&services, wheel), Some(Vec3::new(1.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn extension_adds_propagate_once() {
+        let mut app = Application::new("Transform");
+        assert!(!app.has_system("dotrix_core::transform::propagate"));
+        extension(&mut app);
+        assert!(app.has_system("dotrix_core::transform::propagate"));
+        extension(&mut app);
+        // a duplicate would make the ordering option ambiguous
+        app.add_system(
+            System::from(propagate_after)
+                .with(RunLevel::Load)
+                .with(After("dotrix_core::transform::propagate")),
+        );
+    }
+
+    fn propagate_after(_world: Const<World>) {}
+}
@@ -1,8 +1,16 @@
+mod commands;
 mod container;
 
-use std::{any::TypeId, collections::HashMap, marker::PhantomData, vec::Vec};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    vec::Vec,
+};
 
-use container::Container;
+pub use commands::Commands;
+use container::{Container, Ticks};
 
 use crate::{
     count,
@@ -14,12 +22,17 @@ use crate::{
 pub struct World {
     /// Entities container grouped by archetypes
     content: Vec<Container>,
-    /// Index of the container that holds Entity
-    index: HashMap<Entity, usize>,
+    /// Location of the Entity in containers
+    index: HashMap<Entity, Location>,
     /// Spawn counter for Entity ID generation
     counter: u64,
     /// Buffer to track spawned entities
     spawned: Vec<Entity>,
+    /// Current tick for components change tracking
+    tick: u64,
+    /// Tick of the last run of the current reader, [`Changed`] and [`Added`] filters select
+    /// components changed or added after it
+    last_run: u64,
 }
 
 impl World {
@@ -30,6 +43,8 @@ impl World {
             index: HashMap::new(),
             counter: 0,
             spawned: Vec::with_capacity(16),
+            tick: 1,
+            last_run: 0,
         }
     }
 
@@ -89,8 +104,15 @@ impl World {
         for tuple in iter {
             let entity_id = self.counter;
             let entity = Entity::from(entity_id);
-            tuple.store(container, entity_id);
-            self.index.insert(entity, index);
+            let row = container.rows();
+            tuple.store(container, entity_id, self.tick);
+            self.index.insert(
+                entity,
+                Location {
+                    container: index,
+                    row,
+                },
+            );
             self.counter += 1;
             self.spawned.push(entity)
         }
@@ -134,11 +156,64 @@ impl World {
     where
         Q: Query<'w>,
     {
+        let tick = self.tick;
         let iter = self
             .content
             .iter()
             .filter(|&container| Q::matches(container))
-            .flat_map(|container| Q::select(container));
+            .flat_map(move |container| Q::select(container, tick));
+
+        Matches { iter }
+    }
+
+    /// Query entities from the World, that pass the [`Filter`]
+    ///
+    /// Filter is a tuple of [`With`], [`Without`], [`Changed`] and [`Added`] conditions. All of
+    /// them must be satisfied to select an entity. Components are considered as changed, if they
+    /// were added, inserted or mutably accessed since the last run of the system (see
+    /// [`World::tick`]). Components selected by [`Write`] are considered as changed only if
+    /// they were written.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use dotrix_core::{
+    ///     ecs::{Changed, Mut, Without},
+    ///     World,
+    /// };
+    /// // First component
+    /// struct Component1(u32);
+    ///
+    /// // Second component
+    /// struct Component2(u32);
+    ///
+    /// fn my_system(mut world: Mut<World>) {
+    ///     let query = world.query_filtered::<(&mut Component1,), (Without<Component2>,)>();
+    ///     for (cmp1,) in query {
+    ///         cmp1.0 = 0;
+    ///     }
+    ///
+    ///     let query = world.query_filtered::<(&Component1,), (Changed<Component1>,)>();
+    ///     for (cmp1,) in query {
+    ///         println!("Component1 changed to {}", cmp1.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn query_filtered<'w, Q, F>(&'w self) -> impl Iterator<Item = Q::Item> + 'w
+    where
+        Q: Query<'w> + 'w,
+        F: Filter,
+    {
+        let tick = self.tick;
+        let last_run = self.last_run;
+        let iter = self
+            .content
+            .iter()
+            .filter(|&container| Q::matches(container) && F::matches(container))
+            .flat_map(move |container| {
+                (0..container.rows())
+                    .filter(move |&entity_index| F::pass(container, entity_index, last_run))
+                    .map(move |entity_index| Q::pick(container, entity_index, tick))
+            });
 
         Matches { iter }
     }
@@ -163,19 +238,170 @@ impl World {
     ///         println!("Component1({}), Component2({})", c1.0, c2.0);
     ///     }
     /// }
-    pub fn get<'w, Q>(&'w self, entity: Entity) -> Option<Q>
+    pub fn get<'w, Q>(&'w self, entity: Entity) -> Option<Q::Item>
     where
         Q: Query<'w>,
     {
-        self.index.get(&entity).map(|&index| {
-            let entity_offset = self.content[index]
-                .get::<Entity>()
-                .unwrap()
-                .iter()
-                .position(|&e| e == entity)
-                .unwrap();
-            Q::pick(&self.content[index], entity_offset)
-        })
+        self.index
+            .get(&entity)
+            .filter(|location| Q::matches(&self.content[location.container]))
+            .map(|location| Q::pick(&self.content[location.container], location.row, self.tick))
+    }
+
+    /// Inserts a component to an existing entity
+    ///
+    /// If the entity already has a component of the same type, it will be replaced. Otherwise
+    /// the entity is moved to a container of the new archetype, keeping its [`Entity`] id.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use dotrix_core::{
+    ///     ecs::{Entity, Mut},
+    ///     World,
+    /// };
+    /// // First component
+    /// struct Component1(u32);
+    ///
+    /// // Second component
+    /// struct Component2(u32);
+    ///
+    /// fn my_system(mut world: Mut<World>) {
+    ///     let entity = world.spawn(Some((Component1(1),))).first();
+    ///     world.insert(entity, Component2(2));
+    /// }
+    /// ```
+    ///
+    /// Never insert components inside of the query loop. Store entities somewhere instead and
+    /// insert afterwards
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        let Location {
+            container: index,
+            row: entity_index,
+        } = if let Some(location) = self.index.get(&entity) {
+            *location
+        } else {
+            return;
+        };
+
+        let key = TypeId::of::<T>();
+        if self.content[index].has(key) {
+            self.content[index].get_mut::<T>().unwrap()[entity_index] = component;
+            self.content[index].ticks_mut::<T>().unwrap()[entity_index].changed = self.tick;
+            return;
+        }
+
+        let mut keys = self.content[index].keys();
+        keys.push(key);
+        let target_index = self.container_index(&keys).unwrap_or_else(|| {
+            let mut container = self.content[index].derive();
+            container.init::<T>();
+            self.content.push(container);
+            self.content.len() - 1
+        });
+
+        let tick = self.tick;
+        let (source, target) = self.pair_mut(index, target_index);
+        let row = target.rows();
+        let moved = source.move_to(entity_index, target);
+        target.push::<T>(component, tick);
+        self.relocate(entity, target_index, row, moved, entity_index);
+    }
+
+    /// Removes a component from an existing entity and returns it
+    ///
+    /// The entity is moved to a container of the new archetype, keeping its [`Entity`] id.
+    /// Returns `None` if the entity does not exist or has no such component.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use dotrix_core::{
+    ///     ecs::{Entity, Mut},
+    ///     World,
+    /// };
+    /// // First component
+    /// struct Component1(u32);
+    ///
+    /// // Second component
+    /// struct Component2(u32);
+    ///
+    /// fn my_system(mut world: Mut<World>) {
+    ///     let entity = world.spawn(Some((Component1(1), Component2(2)))).first();
+    ///     if let Some(component) = world.remove::<Component2>(entity) {
+    ///         println!("Component2({}) was removed", component.0);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Never remove components inside of the query loop. Store entities somewhere instead and
+    /// remove afterwards
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        let key = TypeId::of::<T>();
+        if key == TypeId::of::<Entity>() {
+            return None;
+        }
+
+        let Location {
+            container: index,
+            row: entity_index,
+        } = *self.index.get(&entity)?;
+        if !self.content[index].has(key) {
+            return None;
+        }
+
+        let keys = self.content[index]
+            .keys()
+            .into_iter()
+            .filter(|k| *k != key)
+            .collect::<Vec<_>>();
+        let target_index = self.container_index(&keys).unwrap_or_else(|| {
+            let mut container = self.content[index].derive();
+            container.exclude(key);
+            self.content.push(container);
+            self.content.len() - 1
+        });
+
+        let (source, target) = self.pair_mut(index, target_index);
+        let row = target.rows();
+        let component = source.take::<T>(entity_index);
+        let moved = source.move_to(entity_index, target);
+        self.relocate(entity, target_index, row, moved, entity_index);
+        component
+    }
+
+    /// Updates the index after the entity was moved to another container
+    ///
+    /// `moved` entity is one, that took the place of the relocated entity in the old container
+    fn relocate(
+        &mut self,
+        entity: Entity,
+        container: usize,
+        row: usize,
+        moved: Option<Entity>,
+        old_row: usize,
+    ) {
+        self.index.insert(entity, Location { container, row });
+        if let Some(location) = moved.and_then(|moved| self.index.get_mut(&moved)) {
+            location.row = old_row;
+        }
+    }
+
+    /// Returns index of the container storing exactly the defined set of components
+    fn container_index(&self, keys: &[TypeId]) -> Option<usize> {
+        self.content
+            .iter()
+            .position(|container| container.matches_exactly(keys))
+    }
+
+    /// Returns mutable references to two different containers
+    fn pair_mut(&mut self, first: usize, second: usize) -> (&mut Container, &mut Container) {
+        assert_ne!(first, second);
+        if first < second {
+            let (head, tail) = self.content.split_at_mut(second);
+            (&mut head[first], &mut tail[0])
+        } else {
+            let (head, tail) = self.content.split_at_mut(first);
+            (&mut tail[0], &mut head[second])
+        }
     }
 
     /// Exiles an entity from the world
@@ -206,31 +432,49 @@ impl World {
     /// Never exile entities inside of the query loop. Store them somewhere instead and exile
     /// afterwards
     pub fn exile(&mut self, entity: Entity) {
-        let index = if let Some(index) = self.index.get(&entity) {
-            *index
+        let location = if let Some(location) = self.index.remove(&entity) {
+            location
         } else {
             return;
         };
 
-        if index >= self.content.len() {
-            return;
+        if let Some(moved) = self.content[location.container].remove(location.row) {
+            if let Some(moved_location) = self.index.get_mut(&moved) {
+                moved_location.row = location.row;
+            }
         }
+    }
 
-        let entity_index = if let Some(entities) = self.content[index].get::<Entity>() {
-            if let Some((index, _)) = entities
-                .iter()
-                .enumerate()
-                .find(|(_index, e)| **e == entity)
-            {
-                index
-            } else {
-                return;
-            }
-        } else {
-            return;
-        };
-        self.content[index].remove(entity_index);
-        self.index.remove(&entity);
+    /// Returns current tick of the world
+    ///
+    /// The tick is advanced before and after each system accessing the [`World`], so
+    /// [`Changed`] and [`Added`] filters select components changed or added since the last run
+    /// of the system, no matter if it happened in the same frame or in the previous one.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Advances the tick of the world
+    ///
+    /// Out of systems [`Changed`] and [`Added`] filters select components changed or added
+    /// since the last call of the method.
+    pub fn advance(&mut self) {
+        self.last_run = self.tick;
+        self.tick += 1;
+    }
+
+    /// Prepares the world for a system run and returns tick of the run
+    ///
+    /// `last_run` is the tick of the previous run of the system
+    pub(crate) fn enter_system(&mut self, last_run: u64) -> u64 {
+        self.tick += 1;
+        self.last_run = last_run;
+        self.tick
+    }
+
+    /// Finishes the system run, so later changes are not stamped with the tick of the run
+    pub(crate) fn exit_system(&mut self) {
+        self.tick += 1;
     }
 
     /// Returns current value of entities counter
@@ -252,6 +496,15 @@ impl World {
     }
 }
 
+/// Location of an Entity in the [`World`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Location {
+    /// Index of the container
+    container: usize,
+    /// Index of the entity in the container
+    row: usize,
+}
+
 unsafe impl Send for World {}
 unsafe impl Sync for World {}
 
@@ -264,7 +517,7 @@ impl Default for World {
 /// Abstraction for Entities with the same set of components
 pub trait Archetype {
     /// Stores archetype in a [`Container`]
-    fn store(self, container: &mut Container, entity_id: u64);
+    fn store(self, container: &mut Container, entity_id: u64, tick: u64);
     /// Prepares the [`Container`] to store the archetype
     fn map(container: &mut Container);
 }
@@ -279,14 +532,82 @@ pub trait Pattern {
 
 /// Abstraction for queries inoked by [`World::query`]
 pub trait Query<'w> {
-    type Iter: Iterator + 'w;
+    type Item;
+    type Iter: Iterator<Item = Self::Item> + 'w;
 
     /// Selects entities from container
-    fn select(container: &'w Container) -> Self::Iter;
+    fn select(container: &'w Container, tick: u64) -> Self::Iter;
     /// Checks if [`Query`] matches the [`Container`]
     fn matches(container: &'w Container) -> bool;
     /// Pick specific entity by its index in container
-    fn pick(container: &'w Container, entity_index: usize) -> Self;
+    fn pick(container: &'w Container, entity_index: usize, tick: u64) -> Self::Item;
+}
+
+/// Abstraction for query filters used by [`World::query_filtered`]
+pub trait Filter {
+    /// Checks if the [`Filter`] matches the [`Container`]
+    fn matches(container: &Container) -> bool;
+    /// Checks if the entity with the index in the [`Container`] passes the [`Filter`]
+    ///
+    /// `last_run` is the tick of the previous run of the reader
+    fn pass(container: &Container, entity_index: usize, last_run: u64) -> bool;
+}
+
+/// Filter selecting entities having the component
+pub struct With<T>(PhantomData<T>);
+
+/// Filter selecting entities without the component
+pub struct Without<T>(PhantomData<T>);
+
+/// Filter selecting entities with the component changed since the last run of the system
+pub struct Changed<T>(PhantomData<T>);
+
+/// Filter selecting entities with the component added since the last run of the system
+pub struct Added<T>(PhantomData<T>);
+
+impl<T: Component> Filter for With<T> {
+    fn matches(container: &Container) -> bool {
+        container.has(TypeId::of::<T>())
+    }
+    fn pass(_: &Container, _: usize, _: u64) -> bool {
+        true
+    }
+}
+
+impl<T: Component> Filter for Without<T> {
+    fn matches(container: &Container) -> bool {
+        !container.has(TypeId::of::<T>())
+    }
+    fn pass(_: &Container, _: usize, _: u64) -> bool {
+        true
+    }
+}
+
+impl<T: Component> Filter for Changed<T> {
+    fn matches(container: &Container) -> bool {
+        container.has(TypeId::of::<T>())
+    }
+    fn pass(container: &Container, entity_index: usize, last_run: u64) -> bool {
+        container.ticks(TypeId::of::<T>()).unwrap()[entity_index].changed > last_run
+    }
+}
+
+impl<T: Component> Filter for Added<T> {
+    fn matches(container: &Container) -> bool {
+        container.has(TypeId::of::<T>())
+    }
+    fn pass(container: &Container, entity_index: usize, last_run: u64) -> bool {
+        container.ticks(TypeId::of::<T>()).unwrap()[entity_index].added > last_run
+    }
+}
+
+impl Filter for () {
+    fn matches(_: &Container) -> bool {
+        true
+    }
+    fn pass(_: &Container, _: usize, _: u64) -> bool {
+        true
+    }
 }
 
 /// Iterator or Query result
@@ -317,11 +638,12 @@ pub struct Zipper<'w, T> {
 
 /// Trait defenition of Selector to control mutability of borrows
 pub trait Selector<'w> {
-    type Iter: Iterator;
+    type Item;
+    type Iter: Iterator<Item = Self::Item>;
     type Component: Component;
 
-    fn borrow(container: &'w Container) -> Self::Iter;
-    fn borrow_by_index(container: &'w Container, entity_index: usize) -> Self;
+    fn borrow(container: &'w Container, tick: u64) -> Self::Iter;
+    fn borrow_by_index(container: &'w Container, entity_index: usize, tick: u64) -> Self::Item;
     fn matches(container: &'w Container) -> bool {
         container.has(TypeId::of::<Self::Component>())
     }
@@ -331,14 +653,15 @@ impl<'w, C> Selector<'w> for &'w C
 where
     C: Component,
 {
+    type Item = &'w C;
     type Iter = std::slice::Iter<'w, C>;
     type Component = C;
 
-    fn borrow(container: &'w Container) -> Self::Iter {
+    fn borrow(container: &'w Container, _tick: u64) -> Self::Iter {
         container.get::<C>().unwrap().iter()
     }
 
-    fn borrow_by_index(container: &'w Container, entity_index: usize) -> Self {
+    fn borrow_by_index(container: &'w Container, entity_index: usize, _tick: u64) -> Self {
         &container.get::<C>().unwrap()[entity_index]
     }
 }
@@ -347,18 +670,146 @@ impl<'w, C> Selector<'w> for &'w mut C
 where
     C: Component,
 {
-    type Iter = std::slice::IterMut<'w, C>;
+    type Item = &'w mut C;
+    type Iter = IterMut<'w, C>;
     type Component = C;
 
-    fn borrow(container: &'w Container) -> Self::Iter {
-        container.get_mut::<C>().unwrap().iter_mut()
+    fn borrow(container: &'w Container, tick: u64) -> Self::Iter {
+        IterMut {
+            items: container.get_mut::<C>().unwrap().iter_mut(),
+            ticks: container.ticks_mut::<C>().unwrap().iter_mut(),
+            tick,
+        }
     }
 
-    fn borrow_by_index(container: &'w Container, entity_index: usize) -> Self {
+    fn borrow_by_index(container: &'w Container, entity_index: usize, tick: u64) -> Self::Item {
+        container.ticks_mut::<C>().unwrap()[entity_index].changed = tick;
         &mut container.get_mut::<C>().unwrap()[entity_index]
     }
 }
 
+/// Iterator over mutable components, marking them as changed
+pub struct IterMut<'w, C> {
+    items: std::slice::IterMut<'w, C>,
+    ticks: std::slice::IterMut<'w, Ticks>,
+    tick: u64,
+}
+
+impl<'w, C> Iterator for IterMut<'w, C> {
+    type Item = &'w mut C;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.items.next()?;
+        if let Some(ticks) = self.ticks.next() {
+            ticks.changed = self.tick;
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+/// Selector of a mutable component, that is marked as changed only when it is written
+///
+/// Components selected as `&mut C` are marked as changed for the [`Changed`] filter as soon as
+/// they are selected. Systems, that write components only sometimes, can select them as
+/// `Write<C>` to keep untouched components out of the filter:
+///
+/// ```no_run
+/// use dotrix_core::{ecs::{Mut, Write}, World};
+///
+/// struct Health(u32);
+///
+/// fn heal(world: Mut<World>) {
+///     for (mut health,) in world.query::<(Write<Health>,)>() {
+///         if health.0 < 100 {
+///             health.0 += 1;
+///         }
+///     }
+/// }
+/// ```
+pub struct Write<C>(PhantomData<C>);
+
+impl<'w, C> Selector<'w> for Write<C>
+where
+    C: Component,
+{
+    type Item = Tracked<'w, C>;
+    type Iter = IterTracked<'w, C>;
+    type Component = C;
+
+    fn borrow(container: &'w Container, tick: u64) -> Self::Iter {
+        IterTracked {
+            items: container.get_mut::<C>().unwrap().iter_mut(),
+            ticks: container.ticks_mut::<C>().unwrap().iter_mut(),
+            tick,
+        }
+    }
+
+    fn borrow_by_index(container: &'w Container, entity_index: usize, tick: u64) -> Self::Item {
+        Tracked {
+            component: &mut container.get_mut::<C>().unwrap()[entity_index],
+            ticks: &mut container.ticks_mut::<C>().unwrap()[entity_index],
+            tick,
+        }
+    }
+}
+
+/// Mutable access to a component selected by [`Write`], marking it as changed on write
+pub struct Tracked<'w, C> {
+    component: &'w mut C,
+    ticks: &'w mut Ticks,
+    tick: u64,
+}
+
+impl<'w, C> Tracked<'w, C> {
+    /// Marks the component as changed and returns mutable reference to it
+    pub fn into_inner(self) -> &'w mut C {
+        self.ticks.changed = self.tick;
+        self.component
+    }
+}
+
+impl<'w, C> Deref for Tracked<'w, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.component
+    }
+}
+
+impl<'w, C> DerefMut for Tracked<'w, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.ticks.changed = self.tick;
+        self.component
+    }
+}
+
+/// Iterator over components selected by [`Write`]
+pub struct IterTracked<'w, C> {
+    items: std::slice::IterMut<'w, C>,
+    ticks: std::slice::IterMut<'w, Ticks>,
+    tick: u64,
+}
+
+impl<'w, C> Iterator for IterTracked<'w, C> {
+    type Item = Tracked<'w, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(Tracked {
+            component: self.items.next()?,
+            ticks: self.ticks.next()?,
+            tick: self.tick,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
 /// Iterator over Vertices Attributes
 pub struct SpawnedIter<'a> {
     iter: std::slice::Iter<'a, Entity>,
@@ -424,12 +875,12 @@ macro_rules! impl_tuples {
             )*
         {
             #[allow(non_snake_case)]
-            fn store(self, container: &mut Container, entity_id: u64) {
+            fn store(self, container: &mut Container, entity_id: u64, tick: u64) {
                 let ($($i,)*) = self;
 
-                container.push::<Entity>(Entity::from(entity_id));
+                container.push::<Entity>(Entity::from(entity_id), tick);
                 $(
-                    container.push::<$i>($i);
+                    container.push::<$i>($i, tick);
                 )*
             }
             fn map(container: &mut Container) {
@@ -457,15 +908,16 @@ macro_rules! impl_tuples {
         where
             $($i: Selector<'w> + 'w,)*
         {
+            type Item = ($($i::Item,)*);
             type Iter = Zipper<'w, ($($i::Iter,)*)>;
             // type Iter = Zipper<'w, ($(std::slice::Iter<'w, $i>,)*)>;
-            fn pick(container: &'w Container, entity_index: usize) -> ($($i,)*) {
-                ($({$i::borrow_by_index(container, entity_index)},)*)
+            fn pick(container: &'w Container, entity_index: usize, tick: u64) -> Self::Item {
+                ($({$i::borrow_by_index(container, entity_index, tick)},)*)
             }
 
-            fn select(container: &'w Container) -> Self::Iter {
+            fn select(container: &'w Container, tick: u64) -> Self::Iter {
                 Zipper {
-                    tuple: ($({$i::borrow(container)},)*),
+                    tuple: ($({$i::borrow(container, tick)},)*),
                     // tuple: ($(container.get::<$i::Component>().unwrap().into_iter(),)*),
                     _phantom: PhantomData,
                 }
@@ -479,6 +931,23 @@ macro_rules! impl_tuples {
             }
         }
 
+        impl<$($i),*> Filter for ($($i,)*)
+        where
+            $($i: Filter,)*
+        {
+            fn matches(container: &Container) -> bool {
+                $(
+                    $i::matches(container)
+                )&&*
+            }
+
+            fn pass(container: &Container, entity_index: usize, last_run: u64) -> bool {
+                $(
+                    $i::pass(container, entity_index, last_run)
+                )&&*
+            }
+        }
+
         #[allow(non_snake_case)]
         impl<'w, $($i),*> Iterator for Zipper<'w, ($($i,)*)>
         where
@@ -501,12 +970,32 @@ macro_rules! impl_tuples {
     }
 }
 
-recursive!(impl_tuples, A, B, C, D, E, F, G, H); //, E, F, G, H, I, J, K, L, M, N, O, P);
+recursive!(impl_tuples, A, B, C, D, E, F, G, H);
+
+/// Archetype of an entity without components
+impl Archetype for () {
+    fn store(self, container: &mut Container, entity_id: u64, tick: u64) {
+        container.push::<Entity>(Entity::from(entity_id), tick);
+    }
+    fn map(container: &mut Container) {
+        container.init::<Entity>();
+    }
+}
+
+impl Pattern for () {
+    fn len() -> usize {
+        0
+    }
+
+    fn matches(_container: &Container) -> bool {
+        true
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use super::World;
-    use crate::ecs::Entity;
+    use super::{World, Write};
+    use crate::ecs::{Added, Changed, Entity, With, Without};
 
     #[derive(Debug, Eq, PartialEq, Copy, Clone)]
     struct Armor(u32);
@@ -634,4 +1123,174 @@ mod tests {
             assert_eq!(ent, &Entity::from(i as u64 + 1));
         }
     }
+
+    #[test]
+    fn insert_and_remove_components() {
+        let mut world = spawn();
+        let entity = Entity::from(1); // Health(80), Speed(10)
+
+        world.insert(entity, Armor(5));
+        assert_eq!(world.query::<(&Entity, &Armor, &Health)>().count(), 2);
+        if let Some((&armor, &health)) = world.get::<(&Armor, &Health)>(entity) {
+            assert_eq!(armor, Armor(5));
+            assert_eq!(health, Health(80));
+        } else {
+            panic!("Entity must have Armor and Health");
+        }
+
+        world.insert(entity, Armor(7));
+        assert_eq!(
+            world.get::<(&Armor,)>(entity).map(|(a,)| *a),
+            Some(Armor(7))
+        );
+
+        assert_eq!(world.remove::<Health>(entity), Some(Health(80)));
+        assert_eq!(world.remove::<Health>(entity), None);
+        assert!(world.get::<(&Health,)>(entity).is_none());
+        assert_eq!(
+            world.get::<(&Armor,)>(entity).map(|(a,)| *a),
+            Some(Armor(7))
+        );
+        assert_eq!(world.query::<(&Health,)>().count(), 1);
+
+        // entity with the same archetype as original one keeps its data
+        world.spawn(Some((Health(10), Speed(20))));
+        world.insert(Entity::from(13), Armor(1));
+        assert_eq!(world.query::<(&Armor, &Speed)>().count(), 2);
+
+        world.exile(entity);
+        assert!(world.get::<(&Armor,)>(entity).is_none());
+        world.insert(entity, Health(1));
+        assert!(world.get::<(&Health,)>(entity).is_none());
+    }
+
+    #[test]
+    fn exile_and_get_swapped_entities() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = world.spawn((0..10).map(|i| (Health(i),))).into();
+
+        world.exile(entities[0]);
+        world.exile(entities[4]);
+        world.insert(entities[9], Armor(9));
+        assert_eq!(world.remove::<Health>(entities[8]), Some(Health(8)));
+
+        for (i, entity) in entities.iter().enumerate() {
+            let health = world.get::<(&Health,)>(*entity).map(|(h,)| *h);
+            match i {
+                0 | 4 | 8 => assert_eq!(health, None),
+                _ => assert_eq!(health, Some(Health(i as u32))),
+            }
+        }
+        assert_eq!(
+            world.get::<(&Armor,)>(entities[9]).map(|(a,)| *a),
+            Some(Armor(9))
+        );
+        assert_eq!(world.query::<(&Health,)>().count(), 7);
+    }
+
+    #[test]
+    fn query_with_and_without() {
+        let world = spawn();
+
+        let query = world.query_filtered::<(&Damage,), (With<Armor>,)>();
+        assert_eq!(query.count(), 2);
+
+        let query = world.query_filtered::<(&Speed,), (Without<Weight>,)>();
+        let speed = query.map(|(speed,)| speed.0).collect::<Vec<_>>();
+        assert_eq!(speed.len(), 2);
+        assert!(speed.contains(&10));
+        assert!(speed.contains(&50));
+
+        let query = world.query_filtered::<(&Entity,), (With<Speed>, Without<Health>)>();
+        assert_eq!(query.count(), 10);
+
+        let query = world.query_filtered::<(&Entity,), ()>();
+        assert_eq!(query.count(), 13);
+    }
+
+    #[test]
+    fn query_changed_and_added() {
+        let mut world = spawn();
+        assert_eq!(
+            world.query_filtered::<(&Speed,), (Added<Speed>,)>().count(),
+            11
+        );
+
+        world.advance();
+        assert_eq!(
+            world.query_filtered::<(&Speed,), (Added<Speed>,)>().count(),
+            0
+        );
+        assert_eq!(
+            world
+                .query_filtered::<(&Speed,), (Changed<Speed>,)>()
+                .count(),
+            0
+        );
+
+        // mutable access marks components as changed
+        for (speed, _) in world.query::<(&mut Speed, &Damage)>() {
+            speed.0 += 1;
+        }
+        if let Some((health,)) = world.get::<(&mut Health,)>(Entity::from(0)) {
+            health.0 -= 10;
+        }
+        world.insert(Entity::from(3), Armor(20));
+        world.insert(Entity::from(2), Armor(1));
+
+        let query = world.query_filtered::<(&Speed,), (Changed<Speed>,)>();
+        assert_eq!(query.map(|(speed,)| speed.0).collect::<Vec<_>>(), vec![51]);
+        assert_eq!(
+            world
+                .query_filtered::<(&Health,), (Changed<Health>,)>()
+                .count(),
+            1
+        );
+        assert_eq!(
+            world
+                .query_filtered::<(&Armor,), (Changed<Armor>,)>()
+                .count(),
+            2
+        );
+        assert_eq!(
+            world.query_filtered::<(&Armor,), (Added<Armor>,)>().count(),
+            1
+        );
+        // reading does not mark components as changed
+        assert_eq!(
+            world
+                .query_filtered::<(&Damage,), (Changed<Damage>,)>()
+                .count(),
+            0
+        );
+
+        // only selected entities are marked by filtered queries
+        world.advance();
+        for (speed,) in world.query_filtered::<(&mut Speed,), (With<Armor>,)>() {
+            speed.0 = 0;
+        }
+        assert_eq!(
+            world
+                .query_filtered::<(&Speed,), (Changed<Speed>,)>()
+                .count(),
+            1
+        );
+
+        // components selected by `Write` are marked only when written
+        world.advance();
+        for (mut speed,) in world.query::<(Write<Speed>,)>() {
+            if speed.0 == 0 {
+                speed.0 = 1;
+            }
+        }
+        if let Some((speed,)) = world.get::<(Write<Speed>,)>(Entity::from(1)) {
+            assert_eq!(speed.0, 10);
+        }
+        assert_eq!(
+            world
+                .query_filtered::<(&Speed,), (Changed<Speed>,)>()
+                .count(),
+            1
+        );
+    }
 }
@@ -0,0 +1,139 @@
+use super::{Archetype, Pattern, World};
+use crate::ecs::{Component, Entity};
+
+type Command = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+/// Service to defer changes of the [`World`]
+///
+/// Systems can record spawning, exiling, insertion and removal of components at any moment,
+/// even inside of a query loop. Recorded commands are applied to the [`World`] in the same
+/// order right after the `Startup` and `Update` run levels.
+///
+/// ```no_run
+/// use dotrix_core::{
+///     ecs::{Const, Entity, Mut},
+///     Commands, World,
+/// };
+/// // First component
+/// struct Health(u32);
+///
+/// // Second component
+/// struct Dead;
+///
+/// fn my_system(world: Const<World>, mut commands: Mut<Commands>) {
+///     for (entity, health) in world.query::<(&Entity, &Health)>() {
+///         if health.0 == 0 {
+///             commands.insert(*entity, Dead);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    /// Constructs new empty instance of the service
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records spawning of single or multiple entities (see [`World::spawn`])
+    pub fn spawn<T, I>(&mut self, iter: I)
+    where
+        T: Archetype + Pattern + Send + Sync + 'static,
+        I: IntoIterator<Item = T>,
+    {
+        let entities = iter.into_iter().collect::<Vec<_>>();
+        if entities.is_empty() {
+            return;
+        }
+        self.queue.push(Box::new(move |world: &mut World| {
+            world.spawn(entities);
+        }));
+    }
+
+    /// Records exiling of the entity (see [`World::exile`])
+    pub fn exile(&mut self, entity: Entity) {
+        self.queue
+            .push(Box::new(move |world: &mut World| world.exile(entity)));
+    }
+
+    /// Records insertion of the component to the entity (see [`World::insert`])
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        self.queue.push(Box::new(move |world: &mut World| {
+            world.insert(entity, component)
+        }));
+    }
+
+    /// Records removal of the component from the entity (see [`World::remove`])
+    pub fn remove<T: Component>(&mut self, entity: Entity) {
+        self.queue.push(Box::new(move |world: &mut World| {
+            world.remove::<T>(entity);
+        }));
+    }
+
+    /// Returns number of recorded commands
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns true if there are no recorded commands
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Discards all recorded commands
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Applies all recorded commands to the world
+    pub fn apply(&mut self, world: &mut World) {
+        for command in self.queue.drain(..) {
+            command(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Commands;
+    use crate::ecs::Entity;
+    use crate::World;
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct Health(u32);
+    struct Dead;
+
+    #[test]
+    fn deferred_changes() {
+        let mut world = World::new();
+        let mut commands = Commands::new();
+        world.spawn((0..4).map(|i| (Health(i),)));
+
+        for (entity, health) in world.query::<(&Entity, &Health)>() {
+            if health.0 % 2 == 0 {
+                commands.insert(*entity, Dead);
+            } else {
+                commands.exile(*entity);
+            }
+        }
+        commands.spawn(Some((Health(100),)));
+        commands.remove::<Health>(Entity::from(2));
+        assert_eq!(commands.len(), 6);
+        assert_eq!(world.query::<(&Dead,)>().count(), 0);
+
+        commands.apply(&mut world);
+
+        assert!(commands.is_empty());
+        assert_eq!(world.query::<(&Dead,)>().count(), 2);
+        assert_eq!(world.query::<(&Health, &Dead)>().count(), 1);
+        assert_eq!(
+            world.get::<(&Health,)>(Entity::from(4)).map(|(h,)| h.0),
+            Some(100)
+        );
+        assert!(world.get::<(&Health,)>(Entity::from(1)).is_none());
+    }
+}
@@ -1,24 +1,90 @@
 use std::{
     any::{Any, TypeId},
+    cell::UnsafeCell,
     collections::HashMap,
 };
 
 use super::Archetype;
-use crate::ecs::Component;
+use crate::ecs::{Component, Entity};
 
 pub struct Container {
     components: HashMap<TypeId, Box<dyn Stripe>>,
 }
 
+/// World ticks, when a component was added and last time mutably accessed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Ticks {
+    /// Tick when the component was added to the entity
+    pub added: u64,
+    /// Tick when the component was changed last time
+    pub changed: u64,
+}
+
+impl Ticks {
+    fn new(tick: u64) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+}
+
+/// Components of the same type and their ticks
+///
+/// Data is wrapped into `UnsafeCell` because queries borrow components mutably from a shared
+/// reference of the [`super::World`]
+struct Column<T> {
+    items: UnsafeCell<Vec<T>>,
+    ticks: UnsafeCell<Vec<Ticks>>,
+}
+
+impl<T> Column<T> {
+    fn new() -> Self {
+        Self {
+            items: UnsafeCell::new(Vec::new()),
+            ticks: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    fn push(&mut self, item: T, ticks: Ticks) {
+        self.items.get_mut().push(item);
+        self.ticks.get_mut().push(ticks);
+    }
+
+    fn swap_remove(&mut self, index: usize) -> (T, Ticks) {
+        (
+            self.items.get_mut().swap_remove(index),
+            self.ticks.get_mut().swap_remove(index),
+        )
+    }
+}
+
 trait Stripe: Any {
     fn remove_by_index(&mut self, index: usize);
+    fn move_by_index(&mut self, index: usize, target: &mut dyn Stripe);
+    fn empty(&self) -> Box<dyn Stripe>;
+    fn ticks(&self) -> &Vec<Ticks>;
     fn as_any_ref(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-impl<T: 'static> Stripe for Vec<T> {
+impl<T: 'static> Stripe for Column<T> {
     fn remove_by_index(&mut self, index: usize) {
-        self.remove(index);
+        self.swap_remove(index);
+    }
+    fn move_by_index(&mut self, index: usize, target: &mut dyn Stripe) {
+        let (item, ticks) = self.swap_remove(index);
+        target
+            .as_any_mut()
+            .downcast_mut::<Column<T>>()
+            .unwrap()
+            .push(item, ticks);
+    }
+    fn empty(&self) -> Box<dyn Stripe> {
+        Box::new(Column::<T>::new())
+    }
+    fn ticks(&self) -> &Vec<Ticks> {
+        unsafe { &*self.ticks.get() }
     }
     fn as_any_ref(&self) -> &dyn Any {
         self
@@ -37,39 +103,62 @@ impl Container {
         result
     }
 
-    pub fn push<T: Component>(&mut self, component: T) {
+    /// Constructs an empty container with the same set of components
+    pub fn derive(&self) -> Self {
+        Self {
+            components: self
+                .components
+                .iter()
+                .map(|(key, stripe)| (*key, stripe.empty()))
+                .collect(),
+        }
+    }
+
+    pub fn push<T: Component>(&mut self, component: T, tick: u64) {
         if let Some(v) = self.components.get_mut(&TypeId::of::<T>()) {
             v.as_any_mut()
-                .downcast_mut::<Vec<T>>()
+                .downcast_mut::<Column<T>>()
                 .unwrap()
-                .push(component)
+                .push(component, Ticks::new(tick))
         }
     }
 
     pub fn init<T: Component>(&mut self) {
         self.components
-            .insert(TypeId::of::<T>(), Box::new(Vec::<T>::new()));
+            .insert(TypeId::of::<T>(), Box::new(Column::<T>::new()));
     }
 
+    fn column<T: Component>(&self) -> Option<&Column<T>> {
+        self.components
+            .get(&TypeId::of::<T>())
+            .map(|v| v.as_any_ref().downcast_ref::<Column<T>>().unwrap())
+    }
+
+    #[allow(clippy::mut_from_ref)]
     pub fn get_mut<T: Component>(&self) -> Option<&mut Vec<T>>
     where
         T: Component,
     {
-        self.components.get(&TypeId::of::<T>()).map(|v| unsafe {
-            let vec_ref = v.as_any_ref().downcast_ref::<Vec<T>>().unwrap();
-            let vec_ptr = vec_ref as *const Vec<T>;
-            let mut_ptr = vec_ptr as *mut Vec<T>;
-            &mut *mut_ptr
-        })
+        self.column::<T>()
+            .map(|column| unsafe { &mut *column.items.get() })
     }
 
     pub fn get<T: Component>(&self) -> Option<&Vec<T>>
     where
         T: Component,
     {
-        self.components
-            .get(&TypeId::of::<T>())
-            .map(|v| v.as_any_ref().downcast_ref::<Vec<T>>().unwrap())
+        self.column::<T>()
+            .map(|column| unsafe { &*column.items.get() })
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn ticks_mut<T: Component>(&self) -> Option<&mut Vec<Ticks>> {
+        self.column::<T>()
+            .map(|column| unsafe { &mut *column.ticks.get() })
+    }
+
+    pub fn ticks(&self, key: TypeId) -> Option<&Vec<Ticks>> {
+        self.components.get(&key).map(|stripe| stripe.ticks())
     }
 
     pub fn has(&self, key: TypeId) -> bool {
@@ -80,10 +169,70 @@ impl Container {
         self.components.len()
     }
 
-    pub fn remove(&mut self, index: usize) {
+    /// Returns number of entities in the container
+    pub fn rows(&self) -> usize {
+        self.get::<Entity>()
+            .map(|entities| entities.len())
+            .unwrap_or(0)
+    }
+
+    /// Removes the entity from the container
+    ///
+    /// The last entity of the container takes place of the removed one and is returned, so its
+    /// index could be updated
+    pub fn remove(&mut self, index: usize) -> Option<Entity> {
         for stripe in self.components.values_mut() {
             stripe.remove_by_index(index);
         }
+        self.moved(index)
+    }
+
+    /// Removes a component stripe from the container
+    pub fn exclude(&mut self, key: TypeId) {
+        self.components.remove(&key);
+    }
+
+    /// Returns true if the container stores exactly the defined set of components
+    pub fn matches_exactly(&self, keys: &[TypeId]) -> bool {
+        self.components.len() == keys.len() && keys.iter().all(|key| self.has(*key))
+    }
+
+    /// Returns list of components stored in the container
+    pub fn keys(&self) -> Vec<TypeId> {
+        self.components.keys().copied().collect()
+    }
+
+    /// Takes a component of the entity out of the container
+    ///
+    /// Other components of the entity must be removed or moved right after that
+    pub fn take<T: Component>(&mut self, index: usize) -> Option<T> {
+        self.components.get_mut(&TypeId::of::<T>()).map(|stripe| {
+            stripe
+                .as_any_mut()
+                .downcast_mut::<Column<T>>()
+                .unwrap()
+                .swap_remove(index)
+                .0
+        })
+    }
+
+    /// Moves components of the entity to the target container
+    ///
+    /// Only components, that the target container has are moved. The last entity of the
+    /// container takes place of the moved one and is returned, so its index could be updated
+    pub fn move_to(&mut self, index: usize, target: &mut Container) -> Option<Entity> {
+        for (key, stripe) in self.components.iter_mut() {
+            if let Some(target_stripe) = target.components.get_mut(key) {
+                stripe.move_by_index(index, target_stripe.as_mut());
+            }
+        }
+        self.moved(index)
+    }
+
+    /// Returns the entity, that was moved to the index by swap-removal
+    fn moved(&self, index: usize) -> Option<Entity> {
+        self.get::<Entity>()
+            .and_then(|entities| entities.get(index).copied())
     }
 }
 
@@ -91,12 +240,15 @@ impl Container {
 mod tests {
     struct Item1(u32);
     struct Item2(u32);
-    use crate::world::container::Container;
+    use crate::ecs::Entity;
+    use crate::world::container::{Container, Ticks};
+    use std::any::TypeId;
+
     #[test]
     fn mutability() {
         let mut c = Container::new::<(Item1, Item2)>();
-        c.push::<Item1>(Item1(123));
-        c.push::<Item2>(Item2(666));
+        c.push::<Item1>(Item1(123), 0);
+        c.push::<Item2>(Item2(666), 0);
 
         for i in c.get_mut::<Item1>().unwrap() {
             i.0 += 198;
@@ -106,4 +258,36 @@ mod tests {
             assert_eq!(i.0, 321);
         }
     }
+
+    #[test]
+    fn migration() {
+        let mut c1 = Container::new::<(Item1, Item2)>();
+        c1.push::<Entity>(Entity::from(0), 0);
+        c1.push::<Entity>(Entity::from(1), 1);
+        c1.push::<Item1>(Item1(1), 0);
+        c1.push::<Item2>(Item2(2), 0);
+        c1.push::<Item1>(Item1(3), 1);
+        c1.push::<Item2>(Item2(4), 1);
+
+        let mut c2 = c1.derive();
+        c2.exclude(TypeId::of::<Item2>());
+
+        let item2 = c1.take::<Item2>(0).unwrap();
+        let moved = c1.move_to(0, &mut c2);
+
+        assert_eq!(item2.0, 2);
+        assert_eq!(moved, Some(Entity::from(1)));
+        assert_eq!(c1.rows(), 1);
+        assert_eq!(c1.get::<Item2>().unwrap()[0].0, 4);
+        assert_eq!(c2.get::<Item1>().unwrap()[0].0, 1);
+        assert_eq!(c2.get::<Entity>().unwrap()[0], Entity::from(0));
+        assert_eq!(c1.get::<Entity>().unwrap()[0], Entity::from(1));
+        assert!(c2.get::<Item2>().is_none());
+
+        assert_eq!(c1.ticks(TypeId::of::<Item1>()).unwrap()[0], Ticks::new(1));
+        assert_eq!(c2.ticks(TypeId::of::<Item1>()).unwrap()[0], Ticks::new(0));
+
+        assert_eq!(c1.remove(0), None);
+        assert_eq!(c1.rows(), 0);
+    }
 }
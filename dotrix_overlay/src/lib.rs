@@ -11,7 +11,7 @@ use dotrix_core::renderer::{
     BindGroup, Binding, Buffer, DepthBufferMode, Pipeline, PipelineLayout, RenderOptions, Sampler,
     Stage,
 };
-use dotrix_core::{Application, Assets, Globals, Input, Renderer, Window};
+use dotrix_core::{Application, Assets, Input, Renderer, Window};
 
 const PIPELINE_LABEL: &str = "dotrix::overlay";
 
@@ -22,6 +22,7 @@ pub use widget::Widget;
 pub struct Overlay {
     providers: HashMap<TypeId, Box<dyn Ui>>,
     uniform: Option<Buffer>,
+    sampler: Option<Sampler>,
 }
 
 unsafe impl Sync for Overlay {}
@@ -157,7 +158,6 @@ pub fn render(
     mut overlay: Mut<Overlay>,
     mut renderer: Mut<Renderer>,
     mut assets: Mut<Assets>,
-    globals: Const<Globals>,
     window: Const<Window>,
 ) {
     let mut overlay_uniform = overlay
@@ -165,6 +165,13 @@ pub fn render(
         .take()
         .unwrap_or_else(|| Buffer::uniform("Overlay Buffer"));
 
+    // widgets textures are not tiled
+    let mut sampler = overlay
+        .sampler
+        .take()
+        .unwrap_or_else(|| Sampler::new("Overlay Sampler").clamp_to_edge());
+    renderer.load_sampler(&mut sampler);
+
     let surface_size = renderer.surface_size();
     let scale_factor = window.scale_factor();
 
@@ -188,18 +195,16 @@ pub fn render(
 
             widget.mesh.load(&renderer);
 
-            if let Some(texture) = assets.get_mut(widget.texture) {
-                texture.load(&renderer);
-            } else {
+            let loaded = assets
+                .get_mut(widget.texture)
+                .map(|texture| texture.load(&renderer))
+                .unwrap_or(false);
+            if !loaded {
                 continue;
             }
 
             if !pipeline.ready(&renderer) {
                 if let Some(shader) = assets.get(pipeline.shader) {
-                    let sampler = globals
-                        .get::<Sampler>()
-                        .expect("Sampler buffer must be loaded");
-
                     let texture = assets.get(widget.texture).expect("Texture must be loaded");
 
                     renderer.bind(
@@ -225,7 +230,7 @@ pub fn render(
                                             Stage::Fragment,
                                             &texture.buffer,
                                         ),
-                                        Binding::Sampler("Sampler", Stage::Fragment, sampler),
+                                        Binding::Sampler("Sampler", Stage::Fragment, &sampler),
                                     ],
                                 ),
                             ],
@@ -243,6 +248,7 @@ pub fn render(
     }
 
     overlay.uniform = Some(overlay_uniform);
+    overlay.sampler = Some(sampler);
 }
 
 #[repr(C)]
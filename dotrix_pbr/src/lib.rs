@@ -1,11 +1,15 @@
 //! Physically based rendering implementation
 
+use dotrix_core::assets::Preprocessor;
 use dotrix_core::ecs::System;
+use dotrix_core::scene::Scenes;
+use dotrix_core::transform;
 use dotrix_core::Application;
 
 mod light;
 mod material;
 mod model;
+mod prefab;
 
 /// Solid models rendering
 pub mod solid;
@@ -14,23 +18,47 @@ pub mod solid;
 pub mod skeletal;
 
 pub use light::{Light, Lights};
-pub use material::Material;
-pub use model::Model;
+pub use material::{Material, MaterialData};
+pub use model::{Model, ModelData};
+pub use prefab::spawn_prefab;
 
 /// Enables PBR Dotrix Extension
+///
+/// Models are rendered by their [`transform::GlobalTransform`], so the extension also adds the
+/// [`transform::propagate`] system, if the application does not have it yet. PBR shader
+/// modules are registered in the preprocessor of `Assets` on startup, so imported WGSL shaders
+/// can use `#include "dotrix::pbr"` as well.
 pub fn extension(app: &mut Application) {
+    transform::extension(app);
     app.add_system(System::from(material::startup));
     app.add_system(System::from(light::startup));
     app.add_system(System::from(light::load));
 
+    let scenes = app.service_or_default::<Scenes>();
+    scenes.register_serde::<Light>("dotrix::pbr::Light");
+    scenes.register::<Material>("dotrix::pbr::Material");
+    scenes.register::<Model>("dotrix::pbr::Model");
+
     solid::extension(app);
     skeletal::extension(app);
 }
 
-pub fn add_pbr_to_shader(source: &str, bind_group: usize, binding: usize) -> String {
-    let pbr_code = include_str!("shaders/pbr.inc.wgsl");
+/// Integrates PBR lighting into preprocessor
+/// The shader code must contain `#include "dotrix::pbr"` directive
+pub fn add_pbr_to_preprocessor(preprocessor: &mut Preprocessor, bind_group: usize, binding: usize) {
+    Lights::add_to_preprocessor(preprocessor, bind_group, binding);
+    preprocessor.add_module("dotrix::pbr", include_str!("shaders/pbr.inc.wgsl"));
+}
 
-    let pbr_lighted_code = Lights::add_to_shader(pbr_code, bind_group, binding);
+/// Integrates PBR lighting into shader
+/// The `source` shader code must contain `{{ include(light) }}` label
+#[deprecated(note = "use `add_pbr_to_preprocessor` and `#include \"dotrix::pbr\"` instead")]
+pub fn add_pbr_to_shader(source: &str, bind_group: usize, binding: usize) -> String {
+    let mut preprocessor = Preprocessor::new();
+    add_pbr_to_preprocessor(&mut preprocessor, bind_group, binding);
+    let pbr_code = preprocessor
+        .process("dotrix::pbr", "#include \"dotrix::pbr\"")
+        .expect("PBR shader code must be valid");
 
-    source.replace("{{ include(light) }}", &pbr_lighted_code)
+    source.replace("{{ include(light) }}", &pbr_code)
 }
@@ -1,13 +1,16 @@
 //! Various implementations of light sources
+use dotrix_core::assets::Preprocessor;
 use dotrix_core::ecs::{Const, Mut};
 use dotrix_core::renderer::Buffer;
 use dotrix_core::{Camera, Color, Globals, Renderer, World};
 
 use dotrix_math::Vec3;
+use serde::{Deserialize, Serialize};
 
 const MAX_LIGHTS: usize = 10;
 
 /// Light component of different types and settings
+#[derive(Serialize, Deserialize)]
 pub enum Light {
     Ambient {
         /// Light source color
@@ -126,19 +129,28 @@ pub struct Lights {
 }
 
 impl Lights {
+    /// Integrates light support into preprocessor
+    /// The shader code must contain `#include "dotrix::light"` directive and then
+    /// `let light_color = calculate_light(world_position, normal);` can be called
+    pub fn add_to_preprocessor(preprocessor: &mut Preprocessor, bind_group: usize, binding: usize) {
+        preprocessor.add_module("dotrix::light", include_str!("shaders/light.inc.wgsl"));
+        preprocessor.define("MAX_LIGHTS", format!("{}u", MAX_LIGHTS));
+        preprocessor.define("LIGHTS_BIND_GROUP", bind_group);
+        preprocessor.define("LIGHTS_BINDING", binding);
+    }
+
     /// Integrates light support into shader
     /// The `source` shader code must contain `{{ include(light) }}` label and then
     /// `let light_color = calculate_light(world_position, normal);` can be called
+    #[deprecated(
+        note = "use `Lights::add_to_preprocessor` and `#include \"dotrix::light\"` instead"
+    )]
     pub fn add_to_shader(source: &str, bind_group: usize, binding: usize) -> String {
-        let bind_group = format!("{:?}", bind_group);
-        let binding = format!("{:?}", binding);
-        let lights_count = format!("{:?}u", MAX_LIGHTS);
-
-        let light_code = include_str!("shaders/light.inc.wgsl");
-
-        let light_code = str::replace(light_code, "{{ max_lights_count }}", &lights_count)
-            .replace("{{ bind_group }}", &bind_group)
-            .replace("{{ binding }}", &binding);
+        let mut preprocessor = Preprocessor::new();
+        Self::add_to_preprocessor(&mut preprocessor, bind_group, binding);
+        let light_code = preprocessor
+            .process("dotrix::light", "#include \"dotrix::light\"")
+            .expect("Light shader code must be valid");
 
         source.replace("{{ include(light) }}", &light_code)
     }
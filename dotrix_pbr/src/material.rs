@@ -1,7 +1,9 @@
-use dotrix_core::assets::Texture;
+use dotrix_core::assets::{self, Texture};
 use dotrix_core::ecs::Mut;
 use dotrix_core::renderer::Buffer;
+use dotrix_core::scene::{LoadContext, SaveContext, Serializable};
 use dotrix_core::{Assets, Color, Id, Renderer};
+use serde::{Deserialize, Serialize};
 
 const DUMMY_TEXTURE: &str = "dotrix::dummy_texture";
 
@@ -25,6 +27,10 @@ pub struct Material {
     pub ao_texture: Id<Texture>,
     /// Id of a normal map asset
     pub normal_texture: Id<Texture>,
+    /// Emissive color, that is added to the lighting result
+    pub emissive: Color,
+    /// Id of an emissive texture asset, multiplied by the emissive color
+    pub emissive_texture: Id<Texture>,
     /// Pipeline buffer
     pub uniform: Buffer,
 }
@@ -41,12 +47,40 @@ impl Default for Material {
             ao: 1.0,
             ao_texture: Id::default(),
             normal_texture: Id::default(),
+            emissive: Color::black(),
+            emissive_texture: Id::default(),
             uniform: Buffer::uniform("Material Buffer"),
         }
     }
 }
 
 impl Material {
+    /// Constructs the [`Material`] from an imported material asset
+    ///
+    /// Textures are referenced by names, so [`Id`]s of them are registered in [`Assets`] even
+    /// if the textures are not loaded yet.
+    pub fn from_asset(material: &assets::Material, assets: &mut Assets) -> Self {
+        let mut texture = |name: &Option<String>| {
+            name.as_deref()
+                .map(|name| assets.register::<Texture>(name))
+                .unwrap_or_default()
+        };
+        Self {
+            texture: texture(&material.texture),
+            albedo: material.albedo,
+            roughness: material.roughness,
+            roughness_texture: texture(&material.roughness_texture),
+            metallic: material.metallic,
+            metallic_texture: texture(&material.metallic_texture),
+            ao: material.ao,
+            ao_texture: texture(&material.ao_texture),
+            normal_texture: texture(&material.normal_texture),
+            emissive: material.emissive,
+            emissive_texture: texture(&material.emissive_texture),
+            ..Default::default()
+        }
+    }
+
     /// Loads the [`Material`] into GPU buffers
     pub fn load(&mut self, renderer: &Renderer, assets: &mut Assets) -> bool {
         let dummy_id = assets
@@ -67,31 +101,26 @@ impl Material {
         if self.normal_texture.is_null() {
             self.normal_texture = dummy_id;
         }
-
-        if let Some(texture) = assets.get_mut(self.texture) {
-            texture.load(renderer);
-        } else {
-            return false;
-        }
-        if let Some(texture) = assets.get_mut(self.roughness_texture) {
-            texture.load(renderer);
-        } else {
-            return false;
+        if self.emissive_texture.is_null() {
+            self.emissive_texture = dummy_id;
         }
-        if let Some(texture) = assets.get_mut(self.metallic_texture) {
-            texture.load(renderer);
-        } else {
-            return false;
-        }
-        if let Some(texture) = assets.get_mut(self.ao_texture) {
-            texture.load(renderer);
-        } else {
-            return false;
-        }
-        if let Some(texture) = assets.get_mut(self.normal_texture) {
-            texture.load(renderer);
-        } else {
-            return false;
+
+        let textures = [
+            self.texture,
+            self.roughness_texture,
+            self.metallic_texture,
+            self.ao_texture,
+            self.normal_texture,
+            self.emissive_texture,
+        ];
+        for id in textures {
+            let loaded = assets
+                .get_mut(id)
+                .map(|texture| texture.load(renderer))
+                .unwrap_or(false);
+            if !loaded {
+                return false;
+            }
         }
 
         let mut has_texture: u32 = 0;
@@ -110,6 +139,9 @@ impl Material {
         if self.normal_texture != dummy_id {
             has_texture |= 0b10000;
         }
+        if self.emissive_texture != dummy_id {
+            has_texture |= 0b100000;
+        }
 
         let uniform = Uniform {
             albedo: self.albedo.into(),
@@ -117,6 +149,7 @@ impl Material {
             roughness: self.roughness,
             metallic: self.metallic,
             ao: self.ao,
+            emissive: self.emissive.into(),
         };
 
         renderer.load_buffer(&mut self.uniform, bytemuck::cast_slice(&[uniform]));
@@ -124,6 +157,64 @@ impl Material {
     }
 }
 
+/// Serializable representation of the [`Material`]
+#[derive(Serialize, Deserialize)]
+pub struct MaterialData {
+    texture: Option<String>,
+    albedo: Color,
+    roughness: f32,
+    roughness_texture: Option<String>,
+    metallic: f32,
+    metallic_texture: Option<String>,
+    ao: f32,
+    ao_texture: Option<String>,
+    normal_texture: Option<String>,
+    emissive: Color,
+    emissive_texture: Option<String>,
+}
+
+impl Serializable for Material {
+    type Data = MaterialData;
+
+    fn save(&self, context: &SaveContext) -> Self::Data {
+        let texture = |id: Id<Texture>| {
+            context
+                .asset(id)
+                .filter(|name| name.as_str() != DUMMY_TEXTURE)
+        };
+        MaterialData {
+            texture: texture(self.texture),
+            albedo: self.albedo,
+            roughness: self.roughness,
+            roughness_texture: texture(self.roughness_texture),
+            metallic: self.metallic,
+            metallic_texture: texture(self.metallic_texture),
+            ao: self.ao,
+            ao_texture: texture(self.ao_texture),
+            normal_texture: texture(self.normal_texture),
+            emissive: self.emissive,
+            emissive_texture: texture(self.emissive_texture),
+        }
+    }
+
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self> {
+        Some(Self {
+            texture: context.asset(data.texture.as_deref()),
+            albedo: data.albedo,
+            roughness: data.roughness,
+            roughness_texture: context.asset(data.roughness_texture.as_deref()),
+            metallic: data.metallic,
+            metallic_texture: context.asset(data.metallic_texture.as_deref()),
+            ao: data.ao,
+            ao_texture: context.asset(data.ao_texture.as_deref()),
+            normal_texture: context.asset(data.normal_texture.as_deref()),
+            emissive: data.emissive,
+            emissive_texture: context.asset(data.emissive_texture.as_deref()),
+            ..Default::default()
+        })
+    }
+}
+
 #[repr(C)]
 #[derive(Default, Debug, Clone, Copy)]
 struct Uniform {
@@ -132,6 +223,7 @@ struct Uniform {
     roughness: f32,
     metallic: f32,
     ao: f32,
+    emissive: [f32; 4],
 }
 
 unsafe impl bytemuck::Zeroable for Uniform {}
@@ -1,6 +1,8 @@
 use dotrix_core::assets::{Assets, Mesh};
 use dotrix_core::renderer::Buffer;
-use dotrix_core::{Id, Renderer, Transform};
+use dotrix_core::scene::{LoadContext, SaveContext, Serializable};
+use dotrix_core::{GlobalTransform, Id, Renderer};
+use serde::{Deserialize, Serialize};
 
 /// Model component
 pub struct Model {
@@ -20,8 +22,8 @@ impl Model {
         }
         true
     }
-    /// Loads data to the transformation buffer
-    pub fn transform(&mut self, renderer: &Renderer, transform: &Transform) {
+    /// Loads world space transformation to the buffer
+    pub fn transform(&mut self, renderer: &Renderer, transform: &GlobalTransform) {
         let transform_matrix = transform.matrix();
         let transform_raw = AsRef::<[f32; 16]>::as_ref(&transform_matrix);
         renderer.load_buffer(&mut self.transform, bytemuck::cast_slice(transform_raw));
@@ -37,6 +39,26 @@ impl Default for Model {
     }
 }
 
+/// Serializable representation of the [`Model`]
+#[derive(Serialize, Deserialize)]
+pub struct ModelData {
+    mesh: Option<String>,
+}
+
+impl Serializable for Model {
+    type Data = ModelData;
+
+    fn save(&self, context: &SaveContext) -> Self::Data {
+        ModelData {
+            mesh: context.asset(self.mesh),
+        }
+    }
+
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self> {
+        Some(Self::from(context.asset::<Mesh>(data.mesh.as_deref())))
+    }
+}
+
 impl From<Id<Mesh>> for Model {
     fn from(mesh: Id<Mesh>) -> Self {
         Self {
@@ -0,0 +1,100 @@
+use dotrix_core::assets::{self, prefab::Primitive, Mesh, Prefab, Skin};
+use dotrix_core::ecs::Entity;
+use dotrix_core::renderer::Pipeline;
+use dotrix_core::{Assets, Id, Parent, Pose, Transform, World};
+
+use crate::{Material, Model};
+
+/// Spawns a [`Prefab`] into the [`World`] as PBR entities
+///
+/// Each prefab node becomes an entity with its local [`Transform`] and a [`Parent`] component,
+/// so the hierarchy is kept. Root nodes are attached to a new entity with the given
+/// transformation, that is returned to control the whole prefab. Nodes with a skin are spawned
+/// as skeletal entities, others as solid ones. If a node has several primitives, each of them
+/// becomes a child entity of the node.
+///
+/// Returns `None` if the prefab is not loaded yet.
+pub fn spawn_prefab(
+    world: &mut World,
+    assets: &mut Assets,
+    prefab: Id<Prefab>,
+    transform: Transform,
+) -> Option<Entity> {
+    let prefab = assets.get(prefab)?.clone();
+    let root = spawn_one(world.spawn(Some((transform,))));
+    let mut entities: Vec<Entity> = Vec::with_capacity(prefab.nodes.len());
+
+    for node in prefab.nodes.iter() {
+        let parent = Parent(node.parent.map(|index| entities[index]).unwrap_or(root));
+        let skin = node
+            .skin
+            .as_deref()
+            .map(|name| assets.register::<Skin>(name));
+
+        let entity = if node.primitives.len() == 1 {
+            spawn_primitive(
+                world,
+                assets,
+                &node.primitives[0],
+                skin,
+                node.transform,
+                parent,
+            )
+        } else {
+            let entity = spawn_one(world.spawn(Some((node.transform, parent))));
+            for primitive in node.primitives.iter() {
+                spawn_primitive(
+                    world,
+                    assets,
+                    primitive,
+                    skin,
+                    Transform::default(),
+                    Parent(entity),
+                );
+            }
+            entity
+        };
+
+        entities.push(entity);
+    }
+
+    Some(root)
+}
+
+fn spawn_primitive(
+    world: &mut World,
+    assets: &mut Assets,
+    primitive: &Primitive,
+    skin: Option<Id<Skin>>,
+    transform: Transform,
+    parent: Parent,
+) -> Entity {
+    let model = Model::from(assets.register::<Mesh>(&primitive.mesh));
+    let material = primitive
+        .material
+        .as_deref()
+        .and_then(|name| assets.find::<assets::Material>(name))
+        .and_then(|id| assets.get(id).cloned())
+        .map(|material| Material::from_asset(&material, assets))
+        .unwrap_or_default();
+    let render = Pipeline::render(Id::default());
+
+    if let Some(skin) = skin {
+        spawn_one(world.spawn(Some((
+            model,
+            Pose::from(skin),
+            material,
+            transform,
+            parent,
+            render,
+        ))))
+    } else {
+        spawn_one(world.spawn(Some((model, material, transform, parent, render))))
+    }
+}
+
+fn spawn_one(mut spawned: impl Iterator<Item = Entity>) -> Entity {
+    spawned
+        .next()
+        .expect("World must return an entity for each spawned item")
+}
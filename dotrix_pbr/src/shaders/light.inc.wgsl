@@ -1,4 +1,4 @@
-let MAX_LIGHTS_COUNT: u32 = {{ max_lights_count }};
+let MAX_LIGHTS_COUNT: u32 = {{ MAX_LIGHTS }};
 
 struct LightCalcOutput {
   light_direction: vec3<f32>;
@@ -44,7 +44,7 @@ struct Light {
     spot: [[stride(64)]] array<SpotLight, MAX_LIGHTS_COUNT>;
 };
 
-[[group({{ bind_group }}), binding({{ binding }})]]
+[[group({{ LIGHTS_BIND_GROUP }}), binding({{ LIGHTS_BINDING }})]]
 var<uniform> u_light: Light;
 
 fn calculate_directional(
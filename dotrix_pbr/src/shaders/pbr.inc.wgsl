@@ -1,7 +1,7 @@
 // Most of this comes from https://learnopengl.com/PBR/Lighting
 let PI: f32 = 3.14159;
 
-{{ include(light) }}
+#include "dotrix::light"
 
 fn distribution_ggx(normal: vec3<f32>, halfway: vec3<f32>, roughness: f32) -> f32
 {
@@ -73,13 +73,14 @@ fn pbr(
   return (refraction_diffuse_fraction * albedo / PI + specular) * light_out.radiance * n_dot_l;
 }
 
-fn calculate_lighting(
+fn calculate_emissive_lighting(
     position: vec3<f32>,
     normal_in: vec3<f32>,
     albedo: vec3<f32>,
     roughness: f32,
     metallic: f32,
     ao: f32,
+    emissive: vec3<f32>,
 ) -> vec4<f32> {
     let camera_position: vec3<f32> = u_light.camera_position.xyz;
     var light_color: vec3<f32> = vec3<f32>(0.);
@@ -108,7 +109,7 @@ fn calculate_lighting(
 
     // Ambient
     let ambient = get_ambient() * albedo * ao;
-    light_color = light_color + ambient;
+    light_color = light_color + ambient + emissive;
 
     // Gamma correct
     light_color = light_color / (light_color + vec3<f32>(1.0));
@@ -116,3 +117,22 @@ fn calculate_lighting(
 
     return vec4<f32>(light_color, 1.0);
 }
+
+fn calculate_lighting(
+    position: vec3<f32>,
+    normal_in: vec3<f32>,
+    albedo: vec3<f32>,
+    roughness: f32,
+    metallic: f32,
+    ao: f32,
+) -> vec4<f32> {
+    return calculate_emissive_lighting(
+        position,
+        normal_in,
+        albedo,
+        roughness,
+        metallic,
+        ao,
+        vec3<f32>(0.),
+    );
+}
@@ -120,6 +120,7 @@ struct Material {
     roughness: f32;
     metallic: f32;
     ao: f32;
+    emissive: vec4<f32>;
 };
 [[group(1), binding(1)]]
 var<uniform> u_material: Material;
@@ -139,10 +140,13 @@ var r_ao_texture: texture_2d<f32>;
 [[group(1), binding(6)]]
 var r_normal_texture: texture_2d<f32>;
 
+[[group(1), binding(8)]]
+var r_emissive_texture: texture_2d<f32>;
+
 [[group(0), binding(1)]]
 var r_sampler: sampler;
 
-{{ include(light) }}
+#include "dotrix::pbr"
 
 fn average(input: vec4<f32>) -> f32 {
   return (input.x + input.y + input.z + input.w) / 4.;
@@ -192,12 +196,20 @@ fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
       normal = in.normal.xyz;
     }
 
-    return calculate_lighting(
+    var emissive: vec3<f32> = u_material.emissive.rgb;
+    if ((u_material.has_texture & 32u) == 32u) {
+        let emissive_texel = textureSample(r_emissive_texture, r_sampler, in.tex_uv).rgb;
+        // Covert from sRGB to linear color space
+        emissive = emissive * pow(emissive_texel, vec3<f32>(2.2));
+    }
+
+    return calculate_emissive_lighting(
         in.world_position.xyz,
         normal,
         albedo.rgb,
         roughness,
         metallic,
         ao,
+        emissive,
     );
 }
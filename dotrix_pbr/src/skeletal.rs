@@ -4,11 +4,13 @@ use dotrix_core::ecs::{Const, Mut, System};
 use dotrix_core::renderer::{
     BindGroup, Binding, DrawArgs, Pipeline, PipelineLayout, Render, RenderOptions, Sampler, Stage,
 };
-use dotrix_core::{Application, Assets, Color, Globals, Id, Pose, Renderer, Transform, World};
+use dotrix_core::{
+    Application, Assets, Color, GlobalTransform, Globals, Id, Pose, Renderer, Transform, World,
+};
 
 use dotrix_math::{Quat, Rad, Rotation3, Vec3};
 
-use crate::{add_pbr_to_shader, Lights, Material, Model};
+use crate::{add_pbr_to_preprocessor, Lights, Material, Model};
 
 pub const PIPELINE_LABEL: &str = "pbr::skeletal";
 
@@ -104,7 +106,7 @@ pub fn render(
         &mut Model,
         &mut Pose,
         &mut Material,
-        &mut Transform,
+        &GlobalTransform,
         &mut Render,
     )>();
 
@@ -146,6 +148,7 @@ pub fn render(
                 let metallic_texture = assets.get(material.metallic_texture).unwrap();
                 let ao_texture = assets.get(material.ao_texture).unwrap();
                 let normal_texture = assets.get(material.normal_texture).unwrap();
+                let emissive_texture = assets.get(material.emissive_texture).unwrap();
 
                 let proj_view = globals
                     .get::<ProjView>()
@@ -205,6 +208,11 @@ pub fn render(
                                         &normal_texture.buffer,
                                     ),
                                     Binding::Uniform("Joints", Stage::Vertex, &pose.uniform),
+                                    Binding::Texture(
+                                        "EmissiveTexture",
+                                        Stage::Fragment,
+                                        &emissive_texture.buffer,
+                                    ),
                                 ],
                             ),
                         ],
@@ -219,15 +227,15 @@ pub fn render(
 }
 
 pub fn startup(mut assets: Mut<Assets>) {
-    let shader = include_str!("shaders/skeletal.wgsl");
-    assets.store_as(
-        Shader {
-            name: String::from(PIPELINE_LABEL),
-            code: add_pbr_to_shader(shader, 0, 2),
-            ..Default::default()
-        },
+    add_pbr_to_preprocessor(&mut assets.preprocessor_mut(), 0, 2);
+
+    let shader = Shader::from_wgsl(
         PIPELINE_LABEL,
-    );
+        include_str!("shaders/skeletal.wgsl"),
+        &assets.preprocessor(),
+    )
+    .expect("PBR shader must be valid");
+    assets.store_as(shader, PIPELINE_LABEL);
 }
 
 pub fn extension(app: &mut Application) {
@@ -4,11 +4,13 @@ use dotrix_core::ecs::{Const, Mut, Priority, System};
 use dotrix_core::renderer::{
     BindGroup, Binding, DrawArgs, Pipeline, PipelineLayout, Render, RenderOptions, Sampler, Stage,
 };
-use dotrix_core::{Application, Assets, Color, Globals, Id, Renderer, Transform, World};
+use dotrix_core::{
+    Application, Assets, Color, GlobalTransform, Globals, Id, Renderer, Transform, World,
+};
 
 use dotrix_math::{Quat, Rad, Rotation3, Vec3};
 
-use crate::{add_pbr_to_shader, Lights, Material, Model};
+use crate::{add_pbr_to_preprocessor, Lights, Material, Model};
 
 pub const PIPELINE_LABEL: &str = "pbr::solid";
 
@@ -103,7 +105,7 @@ pub fn render(
     globals: Const<Globals>,
     world: Const<World>,
 ) {
-    let query = world.query::<(&mut Model, &mut Material, &mut Transform, &mut Render)>();
+    let query = world.query::<(&mut Model, &mut Material, &GlobalTransform, &mut Render)>();
     for (model, material, transform, render) in query {
         if render.pipeline.shader.is_null() {
             render.pipeline.shader = assets.find::<Shader>(PIPELINE_LABEL).unwrap_or_default();
@@ -137,6 +139,7 @@ pub fn render(
                 let metallic_texture = assets.get(material.metallic_texture).unwrap();
                 let ao_texture = assets.get(material.ao_texture).unwrap();
                 let normal_texture = assets.get(material.normal_texture).unwrap();
+                let emissive_texture = assets.get(material.emissive_texture).unwrap();
 
                 let proj_view = globals
                     .get::<ProjView>()
@@ -195,6 +198,11 @@ pub fn render(
                                         Stage::Fragment,
                                         &normal_texture.buffer,
                                     ),
+                                    Binding::Texture(
+                                        "EmissiveTexture",
+                                        Stage::Fragment,
+                                        &emissive_texture.buffer,
+                                    ),
                                 ],
                             ),
                         ],
@@ -209,16 +217,15 @@ pub fn render(
 }
 
 pub fn startup(mut assets: Mut<Assets>) {
-    let shader = include_str!("shaders/solid.wgsl");
-
-    assets.store_as(
-        Shader {
-            name: String::from(PIPELINE_LABEL),
-            code: add_pbr_to_shader(shader, 0, 2),
-            ..Default::default()
-        },
+    add_pbr_to_preprocessor(&mut assets.preprocessor_mut(), 0, 2);
+
+    let shader = Shader::from_wgsl(
         PIPELINE_LABEL,
-    );
+        include_str!("shaders/solid.wgsl"),
+        &assets.preprocessor(),
+    )
+    .expect("PBR shader must be valid");
+    assets.store_as(shader, PIPELINE_LABEL);
 }
 
 pub fn extension(app: &mut Application) {
@@ -6,9 +6,11 @@ use dotrix_core::renderer::{
     BindGroup, Binding, Buffer, DepthBufferMode, DrawArgs, PipelineLayout, Render, RenderOptions,
     Sampler, Stage,
 };
+use dotrix_core::scene::{LoadContext, SaveContext, Scenes, Serializable};
 use dotrix_core::{Application, Assets, Camera, CubeMap, Globals, Renderer, World};
 
 use dotrix_math::Mat4;
+use serde::{Deserialize, Serialize};
 
 pub const PIPELINE_LABEL: &str = "skybox";
 
@@ -36,6 +38,29 @@ impl Default for SkyBox {
     }
 }
 
+/// Serializable representation of the [`SkyBox`]
+#[derive(Serialize, Deserialize)]
+pub struct SkyBoxData {
+    view_range: f32,
+}
+
+impl Serializable for SkyBox {
+    type Data = SkyBoxData;
+
+    fn save(&self, _context: &SaveContext) -> Self::Data {
+        SkyBoxData {
+            view_range: self.view_range,
+        }
+    }
+
+    fn load(data: Self::Data, _context: &mut LoadContext) -> Option<Self> {
+        Some(Self {
+            view_range: data.view_range,
+            ..Default::default()
+        })
+    }
+}
+
 /// Skybox startup system
 pub fn startup(mut assets: Mut<Assets>, renderer: Const<Renderer>) {
     // generate mesh
@@ -193,4 +218,7 @@ unsafe impl bytemuck::Pod for Uniform {}
 pub fn extension(app: &mut Application) {
     app.add_system(System::from(startup));
     app.add_system(System::from(render));
+
+    let scenes = app.service_or_default::<Scenes>();
+    scenes.register::<SkyBox>("dotrix::sky::SkyBox");
 }
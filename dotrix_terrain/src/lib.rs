@@ -5,7 +5,9 @@
 use std::any::Any;
 
 use dotrix_core::assets::Mesh;
+use dotrix_core::scene::{LoadContext, SaveContext, Scenes, Serializable};
 use dotrix_core::{Application, Id, System};
+use serde::{Deserialize, Serialize};
 
 mod generator;
 mod layers;
@@ -31,6 +33,38 @@ pub struct Tile {
     pub loaded: bool,
 }
 
+/// Serializable representation of the [`Tile`]
+#[derive(Serialize, Deserialize)]
+pub struct TileData {
+    x: i32,
+    z: i32,
+    lod: usize,
+    mesh: Option<String>,
+}
+
+impl Serializable for Tile {
+    type Data = TileData;
+
+    fn save(&self, context: &SaveContext) -> Self::Data {
+        TileData {
+            x: self.x,
+            z: self.z,
+            lod: self.lod,
+            mesh: context.asset(self.mesh),
+        }
+    }
+
+    fn load(data: Self::Data, context: &mut LoadContext) -> Option<Self> {
+        Some(Self {
+            x: data.x,
+            z: data.z,
+            lod: data.lod,
+            mesh: context.asset(data.mesh.as_deref()),
+            loaded: false,
+        })
+    }
+}
+
 /// Trait for the terrain heights source
 pub trait Heightmap: Any + Sync + Send {
     /// Returns Y axis value for specified X and Z pair
@@ -79,4 +113,7 @@ pub fn extension(app: &mut Application) {
     app.add_system(System::from(spawn));
     app.add_system(System::from(render));
     app.add_service(Terrain::default());
+
+    let scenes = app.service_or_default::<Scenes>();
+    scenes.register::<Tile>("dotrix::terrain::Tile");
 }
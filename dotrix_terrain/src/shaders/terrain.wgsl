@@ -41,7 +41,7 @@ var r_texture: texture_2d<f32>;
 [[group(0), binding(1)]]
 var r_sampler: sampler;
 
-{{ include(light) }}
+#include "dotrix::light"
 
 struct Layer {
     color: vec4<f32>;
@@ -48,11 +48,14 @@ pub fn startup(mut assets: Mut<Assets>, mut globals: Mut<Globals>, renderer: Con
     globals.set(layers);
 
     // prepare shader
-    let mut shader = Shader {
-        name: String::from(PIPELINE_LABEL),
-        code: Lights::add_to_shader(include_str!("shaders/terrain.wgsl"), 0, 2),
-        ..Default::default()
-    };
+    Lights::add_to_preprocessor(&mut assets.preprocessor_mut(), 0, 2);
+
+    let mut shader = Shader::from_wgsl(
+        PIPELINE_LABEL,
+        include_str!("shaders/terrain.wgsl"),
+        &assets.preprocessor(),
+    )
+    .expect("Terrain shader must be valid");
     shader.load(&renderer);
     assets.store_as(shader, PIPELINE_LABEL);
 }
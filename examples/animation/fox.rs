@@ -30,7 +30,7 @@ pub enum FoxAnimClip {
 pub fn startup(mut world: Mut<World>, mut assets: Mut<Assets>) {
     assets.import("assets/models/Fox.gltf");
 
-    let texture = assets.register::<Texture>("Fox::fox::texture");
+    let texture = assets.register::<Texture>("Fox.image[0]");
     let mesh = assets.register::<Mesh>("Fox::fox::mesh");
     let skin = assets.register::<Skin>("Fox::fox::skin");
 
@@ -24,7 +24,7 @@ pub fn startup(mut world: Mut<World>, mut assets: Mut<Assets>) {
     let wh2_mesh = assets.register::<Mesh>("car::wheel-2::mesh");
     let wh3_mesh = assets.register::<Mesh>("car::wheel-3::mesh");
     let wh4_mesh = assets.register::<Mesh>("car::wheel-4::mesh");
-    let texture = assets.register::<Texture>("car::texture");
+    let texture = assets.register::<Texture>("car.image[0]");
 
     world.spawn(vec![
         (pbr::solid::Entity {
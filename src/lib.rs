@@ -77,6 +77,8 @@ impl Dotrix {
         app.add_service(Assets::default());
         // Camera service
         app.add_service(Camera::default());
+        // Deferred World changes
+        app.add_service(Commands::default());
         // FPS and delta time counter
         app.add_service(Frame::default());
         // Input manager
@@ -85,8 +87,12 @@ impl Dotrix {
         app.add_service(Globals::default());
         // Render manager
         app.add_service(Renderer::default());
+        // Scenes serialization
+        app.add_service(scene::Scenes::default());
         // States stack
         app.add_service(State::default());
+        // Asynchronous tasks
+        app.add_service(Tasks::default());
 
         // Window manager
         app.add_service(Window::default());
@@ -109,6 +115,8 @@ impl Dotrix {
 
         // Calculate skeletal animations
         app.add_system(System::from(animation::skeletal));
+        // Calculate world space transformations
+        transform::extension(&mut app);
 
         // Finalize frame by Renderer
         app.add_system(System::from(renderer::release));
@@ -157,6 +165,21 @@ impl Dotrix {
         let app = self.app.take().unwrap();
         app.run();
     }
+
+    /// Runs defined number of frames without a window (see [`Application::run_frames`])
+    pub fn run_frames(&mut self, frames: usize) {
+        self.app.as_mut().unwrap().run_frames(frames);
+    }
+
+    /// Runs a single frame of defined duration without a window (see [`Application::step`])
+    pub fn step(&mut self, delta: std::time::Duration) {
+        self.app.as_mut().unwrap().step(delta);
+    }
+
+    /// Returns a service of the application
+    pub fn service<T: IntoService>(&mut self) -> &mut T {
+        self.app.as_mut().unwrap().service::<T>()
+    }
 }
 
 /// Trait providing extendablity